@@ -7,7 +7,8 @@ import logging
 from pathlib import Path
 import tempfile
 import shutil
-from openzt_eval.scorers import RustBuildScorer, RustBuildTestCase
+from openzt_eval.scorers import RustBuildScorer
+from openzt_eval.test_cases import RustBuildTestCase
 
 # Set up logging
 logging.basicConfig(level=logging.INFO)
@@ -85,8 +86,7 @@ async def test_rust_build_scorer():
         logger.info("Testing with a correct implementation...")
         
         test_case = RustBuildTestCase(
-            repo_url=f"file://{repo_path}",  # Local repository
-            tag_or_branch="main",
+            local_path=repo_path,  # Local crate, copied before substitution
             file_path="src/lib.rs",
             replacement_target="    // TODO_FIBONACCI: Implement fibonacci function",
             description="Fibonacci implementation test"
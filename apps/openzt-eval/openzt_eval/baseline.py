@@ -0,0 +1,195 @@
+"""Baseline builds of the pristine checkout, for scoring only the diagnostics a response introduces."""
+
+from typing import Any, Dict, List, Optional
+from dataclasses import dataclass, field, asdict, replace
+from collections import Counter
+from pathlib import Path
+import hashlib
+import json
+import logging
+
+from .rust_syntax import parse_items
+
+logger = logging.getLogger(__name__)
+
+# Directories that are not part of the source tree when computing its digest
+IGNORED_DIRS = ("target", ".git")
+
+
+@dataclass
+class Diagnostic:
+    """A fingerprinted error or warning, as stored in a baseline."""
+    fingerprint: str
+    level: str
+    message: str
+    code: Optional[str] = None
+    file_name: Optional[str] = None
+    item: Optional[str] = None
+
+
+@dataclass
+class Baseline:
+    """The diagnostics of the pristine checkout of a repository revision."""
+    key: str
+    build_status: str
+    build: List[Diagnostic] = field(default_factory=list)
+    clippy: Optional[List[Diagnostic]] = None
+    cached: bool = False
+
+    def to_json(self) -> str:
+        data = asdict(self)
+        del data["cached"]
+        return json.dumps(data, indent=1)
+
+    @classmethod
+    def from_json(cls, text: str) -> 'Baseline':
+        data = json.loads(text)
+        clippy = data.get("clippy")
+        return cls(
+            key=data["key"],
+            build_status=data["build_status"],
+            build=[Diagnostic(**d) for d in data.get("build", [])],
+            clippy=[Diagnostic(**d) for d in clippy] if clippy is not None else None,
+        )
+
+
+@dataclass
+class BaselineDelta:
+    """How the diagnostics of a build compare to the baseline."""
+    new: List[Any] = field(default_factory=list)  # BuildMessage objects not in the baseline
+    existing: List[Any] = field(default_factory=list)  # BuildMessage objects already in the baseline
+    fixed: List[Diagnostic] = field(default_factory=list)  # baseline diagnostics that are gone
+
+    def to_metadata(self) -> Dict[str, Any]:
+        return {
+            "new": len(self.new),
+            "existing": len(self.existing),
+            "fixed": [asdict(d) for d in self.fixed],
+        }
+
+
+class BaselineCache:
+    """Baselines keyed by repository and revision, in memory and optionally on disk."""
+
+    def __init__(self, cache_dir: Optional[Path] = None):
+        self.cache_dir = Path(cache_dir) if cache_dir else None
+        self._baselines: Dict[str, Baseline] = {}
+
+    def get(self, key: str) -> Optional[Baseline]:
+        """Look up a baseline, returning None if it hasn't been built yet."""
+        baseline = self._baselines.get(key)
+        if baseline is None and self.cache_dir:
+            path = self._path(key)
+            if path.is_file():
+                try:
+                    baseline = Baseline.from_json(path.read_text(encoding='utf-8'))
+                except (ValueError, KeyError, TypeError) as e:
+                    logger.warning(f"Ignoring unreadable baseline cache entry {path}: {e}")
+                    return None
+                self._baselines[key] = baseline
+        return replace(baseline, cached=True) if baseline is not None else None
+
+    def put(self, baseline: Baseline):
+        """Store a baseline."""
+        self._baselines[baseline.key] = baseline
+        if self.cache_dir:
+            self.cache_dir.mkdir(parents=True, exist_ok=True)
+            self._path(baseline.key).write_text(baseline.to_json(), encoding='utf-8')
+
+    def _path(self, key: str) -> Path:
+        return self.cache_dir / f"{hashlib.sha1(key.encode('utf-8')).hexdigest()}.json"
+
+
+def tree_digest(root: Path) -> str:
+    """Digest of the file names and contents below root, for trees without a VCS revision."""
+    digest = hashlib.sha1()
+    for path in sorted(root.rglob("*")):
+        relative = path.relative_to(root)
+        if relative.parts[0] in IGNORED_DIRS or not path.is_file():
+            continue
+        digest.update(relative.as_posix().encode('utf-8') + b'\0')
+        digest.update(path.read_bytes() + b'\0')
+    return digest.hexdigest()
+
+
+def enclosing_item(source: str, line: int, column: int) -> Optional[str]:
+    """Path of the innermost item containing a 1-based line and column, if any."""
+    lines = source.split('\n')
+    if not 1 <= line <= len(lines):
+        return None
+    offset = sum(len(text) + 1 for text in lines[:line - 1]) + max(column - 1, 0)
+
+    path = None
+    innermost = -1
+    for item in parse_items(source):
+        if item.start <= offset < item.end and item.start > innermost:
+            path, innermost = item.path, item.start
+    return path
+
+
+def fingerprint_messages(messages: List[Any], root_dir: Path) -> List[tuple]:
+    """
+    Fingerprint the errors and warnings of a build.
+
+    Args:
+        messages: BuildMessage objects of the build
+        root_dir: Directory the span file names are relative to
+
+    Returns:
+        (BuildMessage, Diagnostic) pairs, in message order
+    """
+    from cargo_orchestrator.parser import MessageLevel
+
+    sources: Dict[str, Optional[str]] = {}
+    result = []
+    for message in messages:
+        if message.level not in (MessageLevel.ERROR, MessageLevel.WARNING):
+            continue
+        span = message.primary_span
+        item = None
+        if span:
+            if span.file_name not in sources:
+                path = root_dir / span.file_name
+                sources[span.file_name] = path.read_text(encoding='utf-8') if path.is_file() else None
+            if sources[span.file_name] is not None:
+                item = enclosing_item(sources[span.file_name], span.line_start, span.column_start)
+        result.append((message, Diagnostic(
+            fingerprint=message.fingerprint(item),
+            level=message.level.value,
+            message=message.message,
+            code=message.code,
+            file_name=span.file_name if span else None,
+            item=item,
+        )))
+    return result
+
+
+def compare_to_baseline(baseline: List[Diagnostic], messages: List[Any], root_dir: Path) -> BaselineDelta:
+    """
+    Split the errors and warnings of a build into new and already existing ones.
+
+    Fingerprints are compared as multisets, so a second copy of an existing
+    diagnostic in the same item counts as new.
+
+    Args:
+        baseline: The diagnostics of the pristine checkout
+        messages: BuildMessage objects of the build
+        root_dir: Directory the span file names are relative to
+
+    Returns:
+        BaselineDelta with the new, existing and fixed diagnostics
+    """
+    remaining = Counter(d.fingerprint for d in baseline)
+    delta = BaselineDelta()
+    for message, diagnostic in fingerprint_messages(messages, root_dir):
+        if remaining[diagnostic.fingerprint] > 0:
+            remaining[diagnostic.fingerprint] -= 1
+            delta.existing.append(message)
+        else:
+            delta.new.append(message)
+
+    for diagnostic in baseline:
+        if remaining[diagnostic.fingerprint] > 0:
+            remaining[diagnostic.fingerprint] -= 1
+            delta.fixed.append(diagnostic)
+    return delta
@@ -0,0 +1,246 @@
+"""Scorer for rewrites that remove a crate's diagnostics without changing its behavior."""
+
+from typing import Any, Dict, Optional
+from contextlib import ExitStack
+from pathlib import Path
+import difflib
+import logging
+import tempfile
+import threading
+
+from .baseline import Baseline, BaselineDelta, compare_to_baseline
+from .extraction import extract_code
+from .patching import PatchAnswer, PatchError, parse_patch_answer
+from .repositories import CheckoutError
+from .run_scorer import normalize_output
+from .scorers import RustBuildScorer, ScorerResult, feedback_block
+from .test_cases import CaseExpectations, RustCleanupTestCase
+
+logger = logging.getLogger(__name__)
+
+
+class RustCleanupScorer(RustBuildScorer):
+    """Scorer for diagnostic elimination: the fraction of a crate's diagnostics a rewrite removes.
+    
+    The expected JSON holds RustCleanupTestCase data:
+    {
+        "local_path": "test_projects/warning_project",
+        "file_path": "src/main.rs",
+        "args": ["--verbose"]
+    }
+    
+    The diagnostics of the pristine crate are fingerprinted (see baseline.py),
+    so the score is the fraction of them that are gone minus the fraction of
+    new ones, and 0 if the build fails, a test fails or the program's output
+    or exit code changes. The case passes if nothing remains beyond the
+    "expected_errors" and "expected_warnings" budgets in its metadata
+    (default 0); "time_limit" limits the tests and the program.
+    """
+    
+    test_case_class = RustCleanupTestCase
+    
+    def __init__(self, run_timeout: float = 10.0, **kwargs):
+        """Initialize the Rust cleanup scorer.
+        
+        Args:
+            run_timeout: Seconds the program may run before it is killed, unless
+                the test case sets its own timeout
+            **kwargs: Passed on to RustBuildScorer; use_clippy decides whether
+                clippy's diagnostics (which include the compiler's) are the ones
+                to remove, and run_tests whether the crate's tests must still pass
+        """
+        super().__init__(**kwargs)
+        self.name = "rust_cleanup"
+        self.run_timeout = run_timeout
+        # Output of each pristine program, by baseline key
+        self._original_runs: Dict[str, Any] = {}
+    
+    def repair_feedback(self, result: ScorerResult) -> Optional[str]:
+        """Send back the reason, what broke, and the diagnostics that remain."""
+        metadata = result.metadata or {}
+        parts = [f"The rewrite did not pass: {result.reason}."]
+        if metadata.get("failed_tests"):
+            parts.append("Failing tests: " + ", ".join(metadata["failed_tests"]))
+        if metadata.get("output_diff"):
+            parts.append(feedback_block("Original and rewritten output", metadata["output_diff"]))
+        if metadata.get("diagnostics"):
+            parts.append(feedback_block("Remaining diagnostics", "\n\n".join(metadata["diagnostics"])))
+        return "\n\n".join(parts)
+    
+    def _evaluate_with_test_case(self, test_case: RustCleanupTestCase, response: str,
+                                 expectations: Optional[CaseExpectations] = None) -> ScorerResult:
+        """Compare the diagnostics and behavior of the crate before and after the rewrite."""
+        from cargo_orchestrator import BuildStatus
+        
+        expectations = expectations or CaseExpectations()
+        if test_case.timeout is None:
+            test_case.timeout = expectations.time_limit
+        
+        with tempfile.TemporaryDirectory() as temp_dir, ExitStack() as stack:
+            temp_path = Path(temp_dir)
+            
+            try:
+                repo_path, revision = stack.enter_context(self._warm_checkout(test_case, temp_path))
+                
+                run = test_case.run
+                if run is None:
+                    run = bool(test_case.bin) or (repo_path / "src" / "main.rs").is_file()
+                
+                def run_original(baseline: Baseline) -> Any:
+                    if run and baseline.build_status == BuildStatus.SUCCEEDED.value:
+                        return self._original_run(baseline.key, repo_path, test_case)
+                    return None
+                
+                # The pristine crate's diagnostics and output are what the rewrite is measured against
+                try:
+                    answer, patch_report, baseline, original_run = self._apply_answer(
+                        test_case, response, repo_path, revision, baseline_required=True,
+                        inspect_pristine=run_original
+                    )
+                except PatchError as e:
+                    return self._patch_failure(e, repo_path, revision)
+                original = baseline.clippy if self.use_clippy and baseline.clippy is not None else baseline.build
+                
+                build_result = self._run_cargo_build(repo_path)
+                result = self._run_cargo_clippy(repo_path) if self.use_clippy else build_result
+                delta = compare_to_baseline(original, result.messages, repo_path)
+                
+                test_result = None
+                run_result = None
+                if build_result.success:
+                    if self.run_tests:
+                        test_result = self._run_cargo_test(repo_path, time_limit=expectations.time_limit)
+                    if original_run is not None:
+                        run_result = self._run_binary(repo_path, test_case)
+                
+                result = self._calculate_cleanup_score(build_result, delta, len(original), test_result,
+                                                       original_run, run_result, expectations)
+                result.metadata["patch"] = {"kind": answer.kind, "files": patch_report}
+                result.metadata.update(self._provenance(repo_path, revision))
+                return result
+                
+            except CheckoutError:
+                raise
+            except Exception as e:
+                logger.error(f"Error during Rust cleanup evaluation: {e}")
+                return ScorerResult(
+                    score=0.0,
+                    passed=False,
+                    reason=f"Evaluation failed: {str(e)}"
+                )
+    
+    def _patch_answer(self, response: str, test_case: RustCleanupTestCase) -> PatchAnswer:
+        """Read the rewrite as a diff or labelled files, or else as the new content of file_path."""
+        try:
+            answer = parse_patch_answer(response)
+        except PatchError:
+            code = extract_code(response).code if self.extract_response else response
+            if not code.strip():
+                raise PatchError("Empty answer")
+            answer = PatchAnswer(files={test_case.file_path: code})
+        
+        changed = [patch.path for patch in answer.patches] + list(answer.files)
+        for path in changed:
+            if Path(path).as_posix() != Path(test_case.file_path).as_posix():
+                raise PatchError(f"{path}: only {test_case.file_path} may be changed")
+        return answer
+    
+    def _original_run(self, key: str, repo_path: Path, test_case: RustCleanupTestCase) -> Any:
+        """Run the pristine program, once per revision and arguments."""
+        import json
+        key = json.dumps([key, test_case.bin, test_case.args, test_case.stdin])
+        with self._baseline_locks_guard:
+            lock = self._baseline_locks.setdefault(f"run:{key}", threading.Lock())
+        with lock:
+            if key not in self._original_runs:
+                self._original_runs[key] = self._run_binary(repo_path, test_case)
+            return self._original_runs[key]
+    
+    def _run_binary(self, repo_path: Path, test_case: RustCleanupTestCase) -> Any:
+        """Build and run the crate's program and return the result."""
+        builder = self._create_builder(repo_path)
+        result = builder.run(
+            bin=test_case.bin,
+            args=test_case.args,
+            stdin=test_case.stdin,
+            timeout=test_case.timeout or self.run_timeout
+        )
+        logger.info(f"Cargo run completed: success={result.success}, "
+                    f"exit_code={result.exit_code}, timed_out={result.timed_out}")
+        return result
+    
+    def _calculate_cleanup_score(self, build_result: Any, delta: BaselineDelta, original: int,
+                                 test_result: Any, original_run: Any, run_result: Any,
+                                 expectations: CaseExpectations) -> ScorerResult:
+        """Score the diagnostics removed, unless the rewrite broke the build or changed behavior."""
+        from cargo_orchestrator.parser import MessageLevel
+        
+        remaining = delta.new + delta.existing
+        remaining_errors = sum(1 for msg in remaining if msg.level == MessageLevel.ERROR)
+        remaining_warnings = len(remaining) - remaining_errors
+        if original:
+            score = max(0.0, (len(delta.fixed) - len(delta.new)) / original)
+        else:
+            score = 0.0 if delta.new else 1.0
+        
+        metadata = {
+            "build_success": build_result.success,
+            "build_status": build_result.status.value,
+            "original_diagnostics": original,
+            "fixed_diagnostics": len(delta.fixed),
+            "new_diagnostics": len(delta.new),
+            "remaining_errors": remaining_errors,
+            "remaining_warnings": remaining_warnings,
+            "fixed": delta.to_metadata()["fixed"],
+            # The compiler's rendering of what is left, e.g. for sending back to the model
+            "diagnostics": [(msg.rendered or f"{msg.level.value}: {msg.message}").rstrip() for msg in remaining],
+        }
+        if expectations.to_metadata():
+            metadata["expectations"] = expectations.to_metadata()
+        
+        reason_parts = []
+        if not build_result.success:
+            reason_parts.append(f"Build timed out after {self.timeout} seconds" if build_result.timed_out
+                                else f"Build failed with {remaining_errors} errors")
+        if test_result is not None:
+            metadata.update({
+                "test_success": test_result.success,
+                "tests_passed": sum(1 for t in test_result.tests if t.passed),
+                "failed_tests": [t.name for t in test_result.tests if not t.passed],
+            })
+            if not test_result.success:
+                reason_parts.append("Tests fail after the rewrite")
+        if run_result is not None:
+            output_matches = normalize_output(run_result.stdout) == normalize_output(original_run.stdout)
+            metadata.update({
+                "output_matches": output_matches,
+                "exit_code": run_result.exit_code,
+                "original_exit_code": original_run.exit_code,
+            })
+            if not output_matches:
+                metadata["output_diff"] = "\n".join(difflib.unified_diff(
+                    normalize_output(original_run.stdout), normalize_output(run_result.stdout),
+                    "original", "rewritten", lineterm=""
+                ))
+                reason_parts.append("Program output changed")
+            if run_result.exit_code != original_run.exit_code:
+                reason_parts.append(f"Exit code {run_result.exit_code}, originally {original_run.exit_code}")
+        
+        behavior_kept = not reason_parts
+        if not behavior_kept:
+            score = 0.0
+        if delta.new:
+            reason_parts.append(f"{len(delta.new)} new diagnostics")
+        over_budget = (remaining_errors > (expectations.expected_errors or 0)
+                       or remaining_warnings > (expectations.expected_warnings or 0))
+        if remaining:
+            reason_parts.append(f"{len(remaining)} of the diagnostics remain")
+        
+        return ScorerResult(
+            score=score,
+            passed=behavior_kept and not delta.new and not over_budget,
+            reason="; ".join(reason_parts) if reason_parts else (
+                f"Removed all {original} diagnostics without changing behavior"
+            ),
+            metadata=metadata
+        )
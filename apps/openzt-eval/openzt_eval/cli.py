@@ -6,15 +6,19 @@ import asyncio
 import logging
 import sys
 from pathlib import Path
-from typing import List
+from typing import Dict, List
 import json
 
 from rich.console import Console
 from rich.logging import RichHandler
+from rich.table import Table
 
 from .models import ModelLoader, ModelConfig, ModelType
 from .evaluator import Evaluator, EvalCase
 from .scorers import BasicResponseScorer, LengthScorer, ContainsScorer, RustBuildScorer
+from .run_scorer import RustRunScorer
+from .cleanup_scorer import RustCleanupScorer
+from .crate_scorer import RustCrateScorer
 
 console = Console()
 
@@ -38,6 +42,7 @@ def parse_model_spec(spec: str) -> ModelConfig:
         - gpt4:openai::gpt-4
         - claude:anthropic::claude-3-opus
         - custom:custom:https://api.braintrust.dev/v1/proxy:my-model:sk-xxx
+        - gpt4:replay:cassettes/run1.jsonl (responses recorded for gpt4)
     
     For Braintrust proxy usage:
         Set BRAINTRUST_API_KEY environment variable
@@ -57,6 +62,7 @@ def parse_model_spec(spec: str) -> ModelConfig:
         "anthropic": ModelType.ANTHROPIC,
         "gemini": ModelType.GEMINI,
         "custom": ModelType.CUSTOM,
+        "replay": ModelType.REPLAY,
     }
     
     if type_str not in type_map:
@@ -76,6 +82,19 @@ def parse_model_spec(spec: str) -> ModelConfig:
     )
 
 
+def parse_group_penalties(specs: List[str]) -> Dict[str, float]:
+    """Parse clippy group penalties given as GROUP=PENALTY, e.g. correctness=0.5."""
+    from cargo_orchestrator.lints import CLIPPY_GROUPS
+    
+    penalties = {}
+    for spec in specs:
+        group, sep, value = spec.partition("=")
+        if not sep or group not in CLIPPY_GROUPS:
+            raise ValueError(f"Invalid clippy group penalty: {spec}. Format: GROUP=PENALTY, groups: {list(CLIPPY_GROUPS)}")
+        penalties[group] = float(value)
+    return penalties
+
+
 def load_test_cases(file_path: Path) -> List[EvalCase]:
     """Load test cases from a JSON file."""
     with open(file_path) as f:
@@ -95,7 +114,8 @@ def load_test_cases(file_path: Path) -> List[EvalCase]:
                 name=item.get("name", f"case_{len(cases)+1}"),
                 prompt=item["prompt"],
                 expected=item.get("expected"),
-                metadata=item.get("metadata")
+                metadata=item.get("metadata"),
+                reference_solution=item.get("reference_solution")
             ))
         else:
             raise ValueError(f"Invalid test case format: {item}")
@@ -131,6 +151,36 @@ def get_default_test_cases() -> List[EvalCase]:
     ]
 
 
+def create_rust_build_scorer(args, target_dir_slots: int = 1) -> RustBuildScorer:
+    """Create the Rust build scorer configured by the shared scorer options.
+    
+    Raises:
+        ValueError: If a --rust-clippy-group-penalty is malformed
+    """
+    return RustBuildScorer(
+        use_clippy=args.rust_clippy,
+        allow_warnings=not args.rust_strict,
+        error_penalty=1.0,
+        warning_penalty=0.1,
+        clippy_penalty=0.05,
+        run_tests=not args.rust_no_tests,
+        fixtures_root=Path(args.rust_fixtures_root) if args.rust_fixtures_root else None,
+        git_cache_dir=Path(args.rust_git_cache) if args.rust_git_cache else None,
+        extract_response=not args.rust_raw_response,
+        clippy_group_penalties=parse_group_penalties(args.rust_clippy_group_penalty),
+        timeout=args.rust_timeout,
+        memory_limit_mb=args.rust_memory_limit,
+        cpu_time_limit=args.rust_cpu_limit,
+        count_outside_diagnostics=args.rust_count_outside,
+        baseline=args.rust_baseline or bool(args.rust_baseline_cache),
+        baseline_cache_dir=Path(args.rust_baseline_cache) if args.rust_baseline_cache else None,
+        shared_target_dir=Path(args.rust_target_dir) if args.rust_target_dir else None,
+        target_dir_slots=target_dir_slots,
+        template_dir=Path(args.rust_template_dir) if args.rust_template_dir else None,
+        template_link=args.rust_template_link
+    )
+
+
 async def run_evaluation(args):
     """Run the evaluation."""
     # Parse model specifications
@@ -138,6 +188,17 @@ async def run_evaluation(args):
     for spec in args.models:
         try:
             config = parse_model_spec(spec)
+            if args.temperature is not None:
+                config.parameters["temperature"] = args.temperature
+            if args.cassette and config.type != ModelType.REPLAY:
+                # Replay what the cassette has; record the rest from the model unless replaying only
+                config = ModelConfig(
+                    name=config.name,
+                    type=ModelType.REPLAY,
+                    endpoint=args.cassette,
+                    parameters=config.parameters,
+                    record=None if args.replay_only else config
+                )
             model_configs.append(config)
             console.print(f"[green]✓[/green] Parsed model: {config.name} (type: {config.type.value})")
         except ValueError as e:
@@ -172,31 +233,92 @@ async def run_evaluation(args):
         console.print(f"[cyan]Using {len(cases)} default test cases[/cyan]")
     
     # Setup scorers
+    # Every concurrent build of a repository gets its own warm target directory
+    warm_builds = dict(
+        shared_target_dir=Path(args.rust_target_dir) if args.rust_target_dir else None,
+        target_dir_slots=args.max_concurrent_builds,
+        template_dir=Path(args.rust_template_dir) if args.rust_template_dir else None,
+        template_link=args.rust_template_link
+    )
     scorers = [BasicResponseScorer(min_length=args.min_response_length)]
     if args.check_length:
         scorers.append(LengthScorer(min_length=10, max_length=1000))
     if args.rust_build:
-        scorers.append(RustBuildScorer(
+        try:
+            scorers.append(create_rust_build_scorer(args, target_dir_slots=args.max_concurrent_builds))
+        except ValueError as e:
+            console.print(f"[red]✗[/red] {e}")
+            return 1
+    if args.rust_run:
+        scorers.append(RustRunScorer(
+            run_timeout=args.rust_run_timeout,
+            fixtures_root=Path(args.rust_fixtures_root) if args.rust_fixtures_root else None,
+            git_cache_dir=Path(args.rust_git_cache) if args.rust_git_cache else None,
+            extract_response=not args.rust_raw_response,
+            timeout=args.rust_timeout,
+            memory_limit_mb=args.rust_memory_limit,
+            cpu_time_limit=args.rust_cpu_limit,
+            **warm_builds
+        ))
+    if args.rust_cleanup:
+        scorers.append(RustCleanupScorer(
+            use_clippy=args.rust_clippy,
+            run_tests=not args.rust_no_tests,
+            run_timeout=args.rust_run_timeout,
+            fixtures_root=Path(args.rust_fixtures_root) if args.rust_fixtures_root else None,
+            git_cache_dir=Path(args.rust_git_cache) if args.rust_git_cache else None,
+            extract_response=not args.rust_raw_response,
+            timeout=args.rust_timeout,
+            memory_limit_mb=args.rust_memory_limit,
+            cpu_time_limit=args.rust_cpu_limit,
+            baseline_cache_dir=Path(args.rust_baseline_cache) if args.rust_baseline_cache else None,
+            **warm_builds
+        ))
+    if args.rust_crate:
+        try:
+            group_penalties = parse_group_penalties(args.rust_clippy_group_penalty)
+        except ValueError as e:
+            console.print(f"[red]✗[/red] {e}")
+            return 1
+        scorers.append(RustCrateScorer(
             use_clippy=args.rust_clippy,
             allow_warnings=not args.rust_strict,
-            error_penalty=1.0,
-            warning_penalty=0.1,
-            clippy_penalty=0.05
+            run_tests=not args.rust_no_tests,
+            fixtures_root=Path(args.rust_fixtures_root) if args.rust_fixtures_root else None,
+            extract_response=not args.rust_raw_response,
+            clippy_group_penalties=group_penalties,
+            timeout=args.rust_timeout,
+            memory_limit_mb=args.rust_memory_limit,
+            cpu_time_limit=args.rust_cpu_limit,
+            shared_target_dir=warm_builds["shared_target_dir"],
+            target_dir_slots=warm_builds["target_dir_slots"]
         ))
     console.print(f"[cyan]Using {len(scorers)} scorer(s)[/cyan]")
     
     # Create evaluator
-    evaluator = Evaluator(
-        model_loader=loader,
-        scorers=scorers,
-        use_braintrust=not args.no_braintrust,
-        project_name=args.project,
-        use_autoevals=not args.no_autoevals
-    )
+    try:
+        evaluator = Evaluator(
+            model_loader=loader,
+            scorers=scorers,
+            use_braintrust=not args.no_braintrust,
+            project_name=args.project,
+            use_autoevals=not args.no_autoevals,
+            repair_attempts=args.rust_repair_attempts,
+            samples=args.samples,
+            pass_at_k=args.pass_at_k,
+            max_concurrent_requests=args.max_concurrent_requests,
+            max_concurrent_builds=args.max_concurrent_builds
+        )
+    except ValueError as e:
+        console.print(f"[red]✗[/red] {e}")
+        return 1
     
     # Run evaluation
     console.print("\n[bold]Starting evaluation...[/bold]\n")
-    results = await evaluator.evaluate(cases)
+    try:
+        results = await evaluator.evaluate(cases)
+    finally:
+        evaluator.close()
     
     # Print summary
     console.print("\n")
@@ -209,11 +331,16 @@ async def run_evaluation(args):
             output_data.append({
                 "model": result.model_name,
                 "case": result.case_name,
+                "sample": result.sample,
                 "prompt": result.prompt,
                 "response": result.response,
                 "scores": {name: score.score for name, score in result.scores.items()},
                 "passed": result.passed,
                 "duration_ms": result.duration_ms,
+                "attempts": result.attempts,
+                "solved_at": result.solved_at,
+                "provenance": result.provenance,
+                "error": result.error,
                 "timestamp": result.timestamp.isoformat()
             })
         
@@ -224,10 +351,169 @@ async def run_evaluation(args):
     return 0
 
 
-def main():
-    """Main entry point."""
+def run_validation(args) -> int:
+    """Validate Rust build cases: references pass, non-answers fail."""
+    from .validation import validate_cases
+    
+    cases = load_test_cases(Path(args.test_file))
+    if args.case:
+        unknown = set(args.case) - {case.name for case in cases}
+        if unknown:
+            console.print(f"[red]✗[/red] No such cases in {args.test_file}: {sorted(unknown)}")
+            return 1
+        cases = [case for case in cases if case.name in args.case]
+    console.print(f"[cyan]Validating {len(cases)} case(s) from {args.test_file}[/cyan]")
+    
+    try:
+        scorer = create_rust_build_scorer(args)
+    except ValueError as e:
+        console.print(f"[red]✗[/red] {e}")
+        return 1
+    validations = validate_cases(scorer, cases)
+    
+    table = Table(title="Case Validation")
+    table.add_column("Case", style="cyan")
+    table.add_column("Status")
+    table.add_column("Problems")
+    for validation in validations:
+        status = "[green]ok[/green]" if validation.ok else "[red]broken[/red]"
+        table.add_row(validation.name, status, "\n".join(validation.problems))
+    console.print(table)
+    
+    broken = [v.name for v in validations if not v.ok]
+    if broken:
+        console.print(f"[red]{len(broken)} of {len(validations)} case(s) are broken[/red]")
+    else:
+        console.print(f"[green]✓[/green] All {len(validations)} case(s) are valid")
+    
+    if args.output:
+        with open(args.output, "w") as f:
+            json.dump([v.to_dict() for v in validations], f, indent=2)
+        console.print(f"[green]✓[/green] Report saved to {args.output}")
+    
+    return 1 if broken else 0
+
+
+def scorer_options() -> argparse.ArgumentParser:
+    """Options configuring the Rust build scorer, shared by evaluation runs and validate."""
+    parser = argparse.ArgumentParser(add_help=False)
+    
+    parser.add_argument(
+        "--rust-clippy",
+        action="store_true",
+        help="Enable clippy checks in Rust build scorer (requires --rust-build, --rust-cleanup or --rust-crate)"
+    )
+    
+    parser.add_argument(
+        "--rust-clippy-group-penalty",
+        action="append",
+        default=[],
+        metavar="GROUP=PENALTY",
+        help="Score penalty per clippy lint in a clippy group, e.g. correctness=0.5 (repeatable)"
+    )
+    
+    parser.add_argument(
+        "--rust-strict",
+        action="store_true", 
+        help="Fail Rust build scorer on warnings (requires --rust-build or --rust-crate)"
+    )
+    
+    parser.add_argument(
+        "--rust-no-tests",
+        action="store_true",
+        help="Skip cargo test in Rust build scorer (requires --rust-build, --rust-cleanup or --rust-crate)"
+    )
+    
+    parser.add_argument(
+        "--rust-count-outside",
+        action="store_true",
+        help="Also count errors and warnings outside the substituted code (requires --rust-build)"
+    )
+    
+    parser.add_argument(
+        "--rust-baseline",
+        action="store_true",
+        help="Build the unmodified repository first and only count diagnostics it doesn't have (requires --rust-build)"
+    )
+    
+    parser.add_argument(
+        "--rust-baseline-cache",
+        metavar="DIR",
+        help="Directory to cache baseline builds in across runs (implies --rust-baseline)"
+    )
+    
+    parser.add_argument(
+        "--rust-git-cache",
+        metavar="DIR",
+        help="Directory for the mirrors of repo_url repositories (default: ~/.cache/openzt-eval/git)"
+    )
+    
+    parser.add_argument(
+        "--rust-target-dir",
+        metavar="DIR",
+        help="Keep cargo target directories per repository in DIR, so dependencies compile once"
+    )
+    
+    parser.add_argument(
+        "--rust-template-dir",
+        metavar="DIR",
+        help="Keep a prebuilt copy of each repository revision in DIR and start every build from it"
+    )
+    
+    parser.add_argument(
+        "--rust-template-link",
+        choices=["auto", "reflink", "hardlink", "copy"],
+        default="auto",
+        help="How templates are copied (default: auto, reflinks where supported)"
+    )
+    
+    parser.add_argument(
+        "--rust-timeout",
+        type=int,
+        default=300,
+        help="Seconds each cargo build, clippy or test run may take before it is killed (default: 300)"
+    )
+    
+    parser.add_argument(
+        "--rust-memory-limit",
+        type=int,
+        metavar="MB",
+        help="Address space limit in MB for cargo and every process it spawns"
+    )
+    
+    parser.add_argument(
+        "--rust-cpu-limit",
+        type=int,
+        metavar="SECONDS",
+        help="CPU time limit in seconds for cargo and every process it spawns"
+    )
+    
+    parser.add_argument(
+        "--rust-fixtures-root",
+        help="Directory that local_path in Rust test cases is relative to (default: current directory)"
+    )
+    
+    parser.add_argument(
+        "--rust-raw-response",
+        action="store_true",
+        help="Substitute the raw response instead of extracting code from fenced blocks (requires --rust-build or --rust-run)"
+    )
+    
+    parser.add_argument(
+        "--verbose", "-v",
+        action="store_true",
+        help="Enable verbose logging"
+    )
+    
+    return parser
+
+
+def create_parser() -> argparse.ArgumentParser:
+    """Create the parser of evaluation runs and their subcommands."""
+    common = scorer_options()
     parser = argparse.ArgumentParser(
         prog="openzt-eval",
+        parents=[common],
         description="Evaluate language models using braintrust",
         formatter_class=argparse.RawDescriptionHelpFormatter,
         epilog="""
@@ -249,16 +535,22 @@ Examples:
   # Use custom test cases
   openzt-eval --models local:local --test-file tests.json
   
+  # Record responses, then re-score them later without network access
+  openzt-eval --models gpt4:openai --cassette run1.jsonl --rust-build
+  openzt-eval --models gpt4:openai --cassette run1.jsonl --replay-only --rust-build --rust-strict
+  
   # Save results without Braintrust logging
   openzt-eval --models local:local --output results.json --no-braintrust
+  
+  # Check that every case is solvable before running models on it
+  openzt-eval validate rust_eval_tests.json
         """
     )
     
     parser.add_argument(
         "--models",
         nargs="+",
-        required=True,
-        help="Model specifications (format: name:type[:endpoint][:model_id])"
+        help="Model specifications (format: name:type[:endpoint][:model_id]), required unless running a subcommand"
     )
     
     parser.add_argument(
@@ -266,6 +558,56 @@ Examples:
         help="JSON file containing test cases"
     )
     
+    parser.add_argument(
+        "--samples",
+        type=int,
+        default=1,
+        metavar="N",
+        help="Responses to generate and score per model and case (default: 1)"
+    )
+    
+    parser.add_argument(
+        "--pass-at-k",
+        type=int,
+        nargs="+",
+        metavar="K",
+        help="k values to report pass@k for (default: 1 and --samples)"
+    )
+    
+    parser.add_argument(
+        "--max-concurrent-requests",
+        type=int,
+        default=1,
+        metavar="N",
+        help="Model requests to have in flight at once (default: 1)"
+    )
+    
+    parser.add_argument(
+        "--max-concurrent-builds",
+        type=int,
+        default=1,
+        metavar="N",
+        help="Responses to score (build and test) at once, in worker threads (default: 1)"
+    )
+    
+    parser.add_argument(
+        "--temperature",
+        type=float,
+        help="Sampling temperature for all models (default: the model's own, 0.7)"
+    )
+    
+    parser.add_argument(
+        "--cassette",
+        metavar="FILE",
+        help="Replay model responses recorded in FILE, recording those it lacks"
+    )
+    
+    parser.add_argument(
+        "--replay-only",
+        action="store_true",
+        help="With --cassette, fail requests that weren't recorded instead of calling the model"
+    )
+    
     parser.add_argument(
         "--output",
         help="Save results to JSON file"
@@ -309,34 +651,72 @@ Examples:
     )
     
     parser.add_argument(
-        "--rust-clippy",
+        "--rust-run",
         action="store_true",
-        help="Enable clippy checks in Rust build scorer (requires --rust-build)"
+        help="Enable Rust run scorer, which runs complete programs and compares their output"
     )
     
     parser.add_argument(
-        "--rust-strict",
-        action="store_true", 
-        help="Fail Rust build scorer on warnings (requires --rust-build)"
+        "--rust-cleanup",
+        action="store_true",
+        help="Enable Rust cleanup scorer, which scores rewrites by the diagnostics they remove"
     )
     
     parser.add_argument(
-        "--strict",
+        "--rust-crate",
         action="store_true",
-        help="Exit on first model loading error"
+        help="Enable Rust crate scorer, which builds and tests whole crates generated from a spec"
     )
     
     parser.add_argument(
-        "--verbose", "-v",
+        "--rust-run-timeout",
+        type=float,
+        default=10.0,
+        help="Seconds a program may run under the Rust run scorer (default: 10)"
+    )
+    
+    parser.add_argument(
+        "--rust-repair-attempts",
+        type=int,
+        default=1,
+        metavar="N",
+        help="Attempts per case; failed Rust builds send their diagnostics back to the model (default: 1, no repair)"
+    )
+    
+    parser.add_argument(
+        "--strict",
         action="store_true",
-        help="Enable verbose logging"
+        help="Exit on first model loading error"
+    )
+    
+    subparsers = parser.add_subparsers(dest="command", metavar="COMMAND")
+    validate = subparsers.add_parser(
+        "validate",
+        parents=[common],
+        help="Check that every Rust build case is solvable",
+        description="Check that every Rust build case is solvable: its reference_solution must pass, "
+                    "and an empty or todo!() answer must fail"
     )
+    validate.add_argument("test_file", help="JSON file containing Rust build test cases")
+    validate.add_argument("--case", action="append", metavar="NAME",
+                          help="Only validate the case called NAME (repeatable)")
+    validate.add_argument("--output", help="Save the validation report to a JSON file")
     
+    return parser
+
+
+def main():
+    """Main entry point."""
+    parser = create_parser()
     args = parser.parse_args()
+    if args.command is None and not args.models:
+        parser.error("the following arguments are required: --models")
     
     setup_logging(args.verbose)
     
     try:
+        if args.command == "validate":
+            sys.exit(run_validation(args))
         sys.exit(asyncio.run(run_evaluation(args)))
     except KeyboardInterrupt:
         console.print("\n[yellow]Evaluation interrupted[/yellow]")
@@ -0,0 +1,190 @@
+"""Scorer for whole crates generated from a spec."""
+
+from typing import Any, Dict, List, Optional
+from contextlib import ExitStack
+from pathlib import Path
+import logging
+import tempfile
+
+try:
+    import tomllib
+except ModuleNotFoundError:  # Python < 3.11
+    import tomli as tomllib
+
+from .extraction import extract_code
+from .patching import PatchAnswer, PatchError, apply_patch_answer, parse_patch_answer
+from .scorers import RustBuildScorer, ScorerResult
+from .test_cases import CaseExpectations, RustCrateTestCase
+
+logger = logging.getLogger(__name__)
+
+# Where allowed dependencies of generated crates may come from
+CRATES_IO_SOURCES = (
+    "registry+https://github.com/rust-lang/crates.io-index",
+    "sparse+https://index.crates.io/",
+)
+
+
+class RustCrateScorer(RustBuildScorer):
+    """Scorer for whole crates generated from a spec: built, linted and tested in a fresh scaffold.
+    
+    The expected JSON holds RustCrateTestCase data:
+    {
+        "name": "wordcount",
+        "kind": "bin",
+        "allowed_dependencies": ["clap"],
+        "hidden_tests": [{"file_path": "tests/cli.rs", "source": "test_projects/hidden_tests/wordcount_cli.rs"}]
+    }
+    
+    The response must contain the crate's files as fenced blocks labelled
+    with their path (```toml Cargo.toml, ```rust src/main.rs). A Cargo.toml
+    that renames the package or has a dependency that isn't allowed fails
+    the case before it is built. The case's metadata can set expectations,
+    as for RustBuildScorer.
+    """
+    
+    test_case_class = RustCrateTestCase
+    
+    def __init__(self, **kwargs):
+        """Initialize the Rust crate scorer.
+        
+        Args:
+            **kwargs: Passed on to RustBuildScorer. Every diagnostic in the crate
+                counts; warm target directories are shared by crates with the same
+                package name, so that allowed dependencies compile only once
+        """
+        super().__init__(**kwargs)
+        self.name = "rust_crate"
+    
+    def _evaluate_with_test_case(self, test_case: RustCrateTestCase, response: str,
+                                 expectations: Optional[CaseExpectations] = None) -> ScorerResult:
+        """Write the answer into a fresh scaffold, check its manifest, then build and test it."""
+        expectations = expectations or CaseExpectations()
+        with tempfile.TemporaryDirectory() as temp_dir, ExitStack() as stack:
+            repo_path = Path(temp_dir) / test_case.name
+            revision = {"scaffold": test_case.name, "kind": test_case.kind, "edition": test_case.edition}
+            
+            try:
+                self._write_scaffold(repo_path, test_case)
+                stack.enter_context(self._shared_target_dir(test_case, repo_path))
+                
+                try:
+                    answer = self._crate_answer(response, test_case)
+                    files = apply_patch_answer(repo_path, answer)
+                except PatchError as e:
+                    return self._crate_failure("answer", f"Answer rejected: {e}", repo_path, revision)
+                
+                manifest_problems = self._check_manifest(repo_path, test_case)
+                if manifest_problems:
+                    return self._crate_failure("manifest", f"Cargo.toml rejected: {'; '.join(manifest_problems)}",
+                                               repo_path, revision, {"manifest_problems": manifest_problems})
+                
+                build_result = self._run_cargo_build(repo_path)
+                clippy_result = self._run_cargo_clippy(repo_path) if self.use_clippy else None
+                
+                # Hidden tests are added only now, as for RustBuildScorer
+                test_result = None
+                if (self.run_tests or test_case.hidden_tests) and build_result.success:
+                    if test_case.hidden_tests:
+                        self._write_hidden_tests(repo_path, test_case.hidden_tests)
+                    test_result = self._run_cargo_test(repo_path, all_targets=bool(test_case.hidden_tests),
+                                                       time_limit=expectations.time_limit)
+                
+                result = self._calculate_score(build_result, clippy_result, test_case, test_result,
+                                               expectations=expectations)
+                result.metadata["files"] = files
+                result.metadata.update(self._provenance(repo_path, revision))
+                return result
+                
+            except Exception as e:
+                logger.error(f"Error during Rust crate evaluation: {e}")
+                return ScorerResult(
+                    score=0.0,
+                    passed=False,
+                    reason=f"Evaluation failed: {str(e)}"
+                )
+    
+    def _write_scaffold(self, repo_path: Path, test_case: RustCrateTestCase):
+        """Create the crate the way cargo new does, without version control."""
+        (repo_path / "src").mkdir(parents=True)
+        (repo_path / "Cargo.toml").write_text(
+            f'[package]\nname = "{test_case.name}"\nversion = "0.1.0"\n'
+            f'edition = "{test_case.edition}"\n\n[dependencies]\n',
+            encoding='utf-8'
+        )
+        main = 'fn main() {\n    println!("Hello, world!");\n}\n' if test_case.kind == "bin" else ""
+        (repo_path / test_case.main_file).write_text(main, encoding='utf-8')
+    
+    def _crate_answer(self, response: str, test_case: RustCrateTestCase) -> PatchAnswer:
+        """Read the files of the crate, or else the whole answer as its main file."""
+        try:
+            answer = parse_patch_answer(response)
+        except PatchError:
+            if not self.extract_response:
+                raise
+            code = extract_code(response).code
+            if not code.strip():
+                raise PatchError("Empty answer")
+            answer = PatchAnswer(files={test_case.main_file: code})
+        
+        if answer.patches:
+            raise PatchError("Expected whole files labelled with their path, not a diff")
+        for path in answer.files:
+            parts = Path(path).parts
+            if parts != ("Cargo.toml",) and (len(parts) < 2 or parts[0] != "src"):
+                raise PatchError(f"{path}: only Cargo.toml and files under src/ may be written")
+            if ".cargo" in parts:
+                # Cargo configuration can redirect sources, wherever cargo looks for it
+                raise PatchError(f"{path}: cargo configuration may not be written")
+        return answer
+    
+    def _check_manifest(self, repo_path: Path, test_case: RustCrateTestCase) -> List[str]:
+        """Check the package name and that every dependency is allowed, returning the problems."""
+        try:
+            manifest = tomllib.loads((repo_path / "Cargo.toml").read_text(encoding='utf-8'))
+        except tomllib.TOMLDecodeError as e:
+            return [f"Invalid TOML: {e}"]
+        # Both swap the source of a dependency, e.g. an allowed crate for local code,
+        # which cargo metadata --no-deps doesn't show
+        problems = [f"[{table}] is not allowed" for table in ("patch", "replace") if table in manifest]
+        
+        try:
+            metadata = self._create_builder(repo_path).metadata()
+        except RuntimeError as e:
+            return problems + [str(e)]
+        
+        packages = metadata["packages"]
+        if len(packages) != 1:
+            return problems + [f"Expected a single package, found {len(packages)}"]
+        package = packages[0]
+        if package["name"] != test_case.name:
+            problems.append(f"The package must be named {test_case.name}, not {package['name']}")
+        # A build script runs arbitrary code before the crate is built; cargo also picks up
+        # a build.rs next to Cargo.toml without package.build
+        if manifest.get("package", {}).get("build") or any(
+            "custom-build" in target["kind"] for target in package["targets"]
+        ):
+            problems.append("Build scripts are not allowed")
+        
+        allowed = set(test_case.allowed_dependencies or [])
+        for dep in package["dependencies"]:
+            if dep["source"] not in CRATES_IO_SOURCES:
+                problem = f"{dep['name']} is not a crates.io dependency"
+            elif dep["name"] not in allowed:
+                problem = f"{dep['name']} is not an allowed dependency"
+            else:
+                continue
+            # The same crate may be listed as a normal and a dev-dependency
+            if problem not in problems:
+                problems.append(problem)
+        return problems
+    
+    def _crate_failure(self, failure: str, reason: str, repo_path: Path, revision: Dict[str, Any],
+                       details: Optional[Dict[str, Any]] = None) -> ScorerResult:
+        """Score an answer that was rejected before it was built."""
+        metadata = {"failure": failure, **(details or {})}
+        metadata.update(self._provenance(repo_path, revision))
+        return ScorerResult(score=0.0, passed=False, reason=reason, metadata=metadata)
+    
+    def _target_dir_key(self, test_case: RustCrateTestCase) -> str:
+        return f"crate:{test_case.name}"
@@ -2,8 +2,10 @@
 
 from typing import List, Dict, Any, Optional, Union, Callable
 from dataclasses import dataclass, field
+from concurrent.futures import ThreadPoolExecutor
 import asyncio
 import logging
+import platform
 from datetime import datetime
 
 try:
@@ -22,7 +24,8 @@ except ImportError:
     AUTOEVALS_AVAILABLE = False
 
 from .models import ModelLoader, ModelConfig
-from .scorers import BaseScorer, BasicResponseScorer, RustBuildScorer, ScorerResult
+from .scorers import BaseScorer, BasicResponseScorer, ScorerResult
+from .metrics import pass_at_k_by_group
 
 logger = logging.getLogger(__name__)
 
@@ -34,6 +37,8 @@ class EvalCase:
     prompt: str
     expected: Optional[str] = None
     metadata: Optional[Dict[str, Any]] = None
+    # A known-good answer, never shown to models; a dict answers each slot by name
+    reference_solution: Optional[Union[str, Dict[str, str]]] = None
 
 
 @dataclass
@@ -47,11 +52,16 @@ class EvalResult:
     duration_ms: float
     timestamp: datetime = field(default_factory=datetime.now)
     metadata: Optional[Dict[str, Any]] = None
+    attempts: List[Dict[str, Any]] = field(default_factory=list)
+    solved_at: Optional[int] = None  # 1-based attempt that passed, if any
+    sample: int = 0  # index among the samples generated for this model and case
+    provenance: Dict[str, Any] = field(default_factory=dict)  # host, toolchain and revision under test
+    error: Optional[str] = None  # why the case couldn't be evaluated, e.g. a failed checkout
     
     @property
     def passed(self) -> bool:
         """Check if all scorers passed."""
-        return all(score.passed for score in self.scores.values())
+        return self.error is None and all(score.passed for score in self.scores.values())
     
     @property
     def average_score(self) -> float:
@@ -70,13 +80,48 @@ class Evaluator:
         scorers: Optional[List[Union[BaseScorer, Callable]]] = None,
         use_braintrust: bool = True,
         project_name: str = "openzt-eval",
-        use_autoevals: bool = True
+        use_autoevals: bool = True,
+        repair_attempts: int = 1,
+        samples: int = 1,
+        pass_at_k: Optional[List[int]] = None,
+        max_concurrent_requests: int = 1,
+        max_concurrent_builds: int = 1
     ):
+        """Create an evaluator.
+        
+        Args:
+            repair_attempts: Responses to request per case at most. While a Rust
+                build scorer fails, its rendered compiler diagnostics are sent back
+                to the model as a follow-up chat turn; 1 disables repair.
+            samples: Independent samples to generate and score per model and case.
+                The sampling temperature comes from ModelConfig.parameters.
+            pass_at_k: The k values print_summary reports pass@k for
+                (default: 1 and samples)
+            max_concurrent_requests: Model requests that may be in flight at once
+            max_concurrent_builds: Responses that may be scored at once. Scoring
+                runs in worker threads, so cargo doesn't block the event loop.
+        """
+        if repair_attempts < 1:
+            raise ValueError("repair_attempts must be at least 1")
+        if samples < 1:
+            raise ValueError("samples must be at least 1")
+        if max_concurrent_requests < 1 or max_concurrent_builds < 1:
+            raise ValueError("concurrency limits must be at least 1")
+        ks = sorted(set(pass_at_k or [1, samples]))
+        if ks[0] < 1 or ks[-1] > samples:
+            raise ValueError(f"pass@k needs 1 <= k <= samples ({samples}), got {ks}")
         self.model_loader = model_loader
         self.scorers = scorers or [BasicResponseScorer()]
         self.use_braintrust = use_braintrust and braintrust is not None
         self.project_name = project_name
         self.use_autoevals = use_autoevals and AUTOEVALS_AVAILABLE
+        self.repair_attempts = repair_attempts
+        self.samples = samples
+        self.pass_at_k = ks
+        self.max_concurrent_requests = max_concurrent_requests
+        self._request_semaphore: Optional[asyncio.Semaphore] = None
+        self._semaphore_loop = None
+        self.build_executor = ThreadPoolExecutor(max_workers=max_concurrent_builds, thread_name_prefix="scorer")
         self.autoevals_scorers = []
         
         # Add default autoevals scorers if available
@@ -106,52 +151,57 @@ class Evaluator:
     async def evaluate_case(
         self,
         model_name: str,
-        case: EvalCase
+        case: EvalCase,
+        sample: int = 0
     ) -> EvalResult:
-        """Evaluate a single test case on a model."""
+        """Evaluate a single test case on a model, for one sample."""
         model = self.model_loader.get_model(model_name)
         if not model:
             raise ValueError(f"Model {model_name} not loaded")
         
-        # Generate response
-        start_time = asyncio.get_event_loop().time()
-        try:
-            response = await model.generate(case.prompt)
-        except Exception as e:
-            logger.error(f"Error generating response for {model_name}: {e}")
-            response = f"ERROR: {str(e)}"
-        duration_ms = (asyncio.get_event_loop().time() - start_time) * 1000
-        
-        # Score the response with custom scorers
-        scores = {}
-        for scorer in self.scorers:
-            if isinstance(scorer, BaseScorer):
-                scores[scorer.name] = scorer.score(case.prompt, response, case.expected)
-            elif callable(scorer):
-                # Handle autoevals or other callable scorers
-                try:
-                    result = scorer(output=response, expected=case.expected)
-                    scores[getattr(scorer, '__name__', 'custom')] = ScorerResult(
-                        score=result.score if hasattr(result, 'score') else result,
-                        passed=result.score >= 0.5 if hasattr(result, 'score') else result > 0.5,
-                        reason=result.rationale if hasattr(result, 'rationale') else None
-                    )
-                except Exception as e:
-                    logger.warning(f"Error running scorer {scorer}: {e}")
+        messages = [{"role": "user", "content": case.prompt}]
+        attempts = []
+        duration_ms = 0.0
+        solved_at = None
         
-        # Apply autoevals scorers if expected output exists
-        if self.use_autoevals and case.expected:
-            for name, scorer_class in self.autoevals_scorers:
-                try:
-                    result = scorer_class()(output=response, expected=case.expected)
-                    scores[name] = ScorerResult(
-                        score=result.score,
-                        passed=result.score >= 0.5,
-                        reason=getattr(result, 'rationale', None),
-                        metadata=result.metadata if hasattr(result, 'metadata') else None
-                    )
-                except Exception as e:
-                    logger.warning(f"Error running autoevals scorer {name}: {e}")
+        for attempt in range(1, self.repair_attempts + 1):
+            # Generate response; follow-up turns carry the whole conversation
+            start_time = asyncio.get_event_loop().time()
+            failed = False
+            try:
+                async with self._request_slots():
+                    if attempt == 1:
+                        response = await model.generate(case.prompt)
+                    else:
+                        response = await model.chat(messages)
+            except Exception as e:
+                logger.error(f"Error generating response for {model_name}: {e}")
+                response = f"ERROR: {str(e)}"
+                failed = True
+            attempt_ms = (asyncio.get_event_loop().time() - start_time) * 1000
+            duration_ms += attempt_ms
+            
+            scores = await asyncio.get_running_loop().run_in_executor(
+                self.build_executor, self._score_response, case, response
+            )
+            passed = all(score.passed for score in scores.values())
+            attempts.append({
+                "attempt": attempt,
+                "response": response,
+                "scores": {name: score.score for name, score in scores.items()},
+                "passed": passed,
+                "duration_ms": attempt_ms
+            })
+            if passed:
+                solved_at = attempt
+                break
+            
+            feedback = None if failed else self._repair_feedback(scores)
+            if feedback is None or attempt == self.repair_attempts:
+                break
+            logger.info(f"  Attempt {attempt} of {case.name} failed; sending the compiler feedback back")
+            messages.append({"role": "assistant", "content": response})
+            messages.append({"role": "user", "content": feedback})
         
         result = EvalResult(
             model_name=model_name,
@@ -160,7 +210,11 @@ class Evaluator:
             response=response,
             scores=scores,
             duration_ms=duration_ms,
-            metadata=case.metadata
+            metadata=case.metadata,
+            attempts=attempts,
+            solved_at=solved_at,
+            sample=sample,
+            provenance=self._provenance(scores)
         )
         
         # Log to Braintrust if enabled
@@ -176,6 +230,10 @@ class Evaluator:
                         "case": case.name,
                         "duration_ms": duration_ms,
                         "passed": result.passed,
+                        "attempts": len(attempts),
+                        "solved_at": solved_at,
+                        "sample": sample,
+                        "provenance": result.provenance,
                         **(case.metadata or {})
                     }
                 )
@@ -184,6 +242,73 @@ class Evaluator:
         
         return result
     
+    def _request_slots(self) -> asyncio.Semaphore:
+        """The semaphore bounding model requests, created for the running event loop."""
+        loop = asyncio.get_running_loop()
+        if self._semaphore_loop is not loop:
+            self._request_semaphore = asyncio.Semaphore(self.max_concurrent_requests)
+            self._semaphore_loop = loop
+        return self._request_semaphore
+    
+    def _score_response(self, case: EvalCase, response: str) -> Dict[str, ScorerResult]:
+        """Score a response with the custom scorers and, if enabled, autoevals."""
+        scores = {}
+        for scorer in self.scorers:
+            if isinstance(scorer, BaseScorer):
+                scores[scorer.name] = scorer.score(case.prompt, response, case.expected, case.metadata)
+            elif callable(scorer):
+                # Handle autoevals or other callable scorers
+                try:
+                    result = scorer(output=response, expected=case.expected)
+                    scores[getattr(scorer, '__name__', 'custom')] = ScorerResult(
+                        score=result.score if hasattr(result, 'score') else result,
+                        passed=result.score >= 0.5 if hasattr(result, 'score') else result > 0.5,
+                        reason=result.rationale if hasattr(result, 'rationale') else None
+                    )
+                except Exception as e:
+                    logger.warning(f"Error running scorer {scorer}: {e}")
+        
+        # Apply autoevals scorers if expected output exists
+        if self.use_autoevals and case.expected:
+            for name, scorer_class in self.autoevals_scorers:
+                try:
+                    result = scorer_class()(output=response, expected=case.expected)
+                    scores[name] = ScorerResult(
+                        score=result.score,
+                        passed=result.score >= 0.5,
+                        reason=getattr(result, 'rationale', None),
+                        metadata=result.metadata if hasattr(result, 'metadata') else None
+                    )
+                except Exception as e:
+                    logger.warning(f"Error running autoevals scorer {name}: {e}")
+        
+        return scores
+    
+    def _provenance(self, scores: Dict[str, ScorerResult]) -> Dict[str, Any]:
+        """Record what produced a result: the host, and the toolchain and revision the scorers built."""
+        provenance = {"platform": platform.platform(), "python": platform.python_version()}
+        for score in scores.values():
+            for key in ("toolchain", "revision"):
+                if score.metadata and key in score.metadata:
+                    provenance.setdefault(key, score.metadata[key])
+        return provenance
+    
+    def _repair_feedback(self, scores: Dict[str, ScorerResult]) -> Optional[str]:
+        """Build the follow-up turn from the feedback of the failed scorers, or None if none gives any."""
+        parts = []
+        for scorer in self.scorers:
+            result = scores.get(scorer.name)
+            if result is None or result.passed:
+                continue
+            feedback = scorer.repair_feedback(result)
+            if feedback:
+                parts.append(feedback)
+        
+        if not parts:
+            return None
+        parts.append("Please fix the code and reply with the complete corrected answer in the same format as before.")
+        return "\n\n".join(parts)
+    
     async def evaluate(
         self,
         cases: List[EvalCase],
@@ -193,23 +318,53 @@ class Evaluator:
         if model_names is None:
             model_names = list(self.model_loader.models.keys())
         
-        results = []
-        total = len(cases) * len(model_names)
+        total = len(cases) * len(model_names) * self.samples
         completed = 0
         
-        for model_name in model_names:
-            logger.info(f"Evaluating model: {model_name}")
-            for case in cases:
-                logger.info(f"  Running case: {case.name}")
-                result = await self.evaluate_case(model_name, case)
-                results.append(result)
-                
-                completed += 1
-                logger.info(
-                    f"  [{completed}/{total}] {model_name}/{case.name}: "
-                    f"{'PASS' if result.passed else 'FAIL'} "
-                    f"(score: {result.average_score:.2f})"
+        async def run(model_name: str, case: EvalCase, sample: int) -> EvalResult:
+            nonlocal completed
+            try:
+                result = await self.evaluate_case(model_name, case, sample)
+            except Exception as e:
+                # e.g. a CheckoutError; the case is broken, but the other results stand
+                logger.error(f"Error evaluating {model_name}/{case.name}: {e}")
+                result = EvalResult(
+                    model_name=model_name,
+                    case_name=case.name,
+                    prompt=case.prompt,
+                    response="",
+                    scores={},
+                    duration_ms=0.0,
+                    metadata=case.metadata,
+                    sample=sample,
+                    error=f"{type(e).__name__}: {e}"
                 )
+            completed += 1
+            label = f"{case.name}#{sample + 1}" if self.samples > 1 else case.name
+            logger.info(
+                f"  [{completed}/{total}] {model_name}/{label}: "
+                f"{'PASS' if result.passed else 'FAIL'} "
+                f"(score: {result.average_score:.2f})"
+            )
+            return result
+        
+        # Everything is started at once; the semaphore and the executor bound the
+        # actual concurrency, and gather keeps the results in submission order
+        logger.info(f"Evaluating models: {', '.join(model_names)}")
+        tasks = [
+            asyncio.ensure_future(run(model_name, case, sample))
+            for model_name in model_names
+            for case in cases
+            for sample in range(self.samples)
+        ]
+        try:
+            results = list(await asyncio.gather(*tasks))
+        except BaseException:
+            # Interrupted: stop the model requests and drop the builds that haven't started
+            for task in tasks:
+                task.cancel()
+            self.build_executor.shutdown(wait=False, cancel_futures=True)
+            raise
         
         # Finalize Braintrust experiment if used
         if self.use_braintrust and self.bt_project:
@@ -221,6 +376,10 @@ class Evaluator:
         
         return results
     
+    def close(self):
+        """Shut down the scoring threads, waiting for running builds to finish."""
+        self.build_executor.shutdown(wait=True, cancel_futures=True)
+    
     def print_summary(self, results: List[EvalResult]):
         """Print a summary of evaluation results."""
         from rich.console import Console
@@ -243,6 +402,12 @@ class Evaluator:
         table.add_column("Failed", justify="right", style="red")
         table.add_column("Avg Score", justify="right")
         table.add_column("Avg Duration (ms)", justify="right")
+        for k in self.pass_at_k:
+            table.add_column(f"pass@{k}", justify="right")
+        
+        by_model_pass_at_k = pass_at_k_by_group(
+            ((r.model_name, r.case_name, r.passed) for r in results), self.pass_at_k
+        )
         
         for model_name, model_results in by_model.items():
             passed = sum(1 for r in model_results if r.passed)
@@ -256,7 +421,38 @@ class Evaluator:
                 str(passed),
                 str(failed),
                 f"{avg_score:.2f}",
-                f"{avg_duration:.1f}"
+                f"{avg_duration:.1f}",
+                *(_format_rate(by_model_pass_at_k[model_name][k]) for k in self.pass_at_k)
             )
         
-        console.print(table)
\ No newline at end of file
+        console.print(table)
+        
+        for result in results:
+            if result.error:
+                console.print(f"[red]Not evaluated[/red] {result.model_name}/{result.case_name}: {result.error}")
+        
+        # Break pass@k down by the category in the case metadata, if the cases have one
+        if not any((r.metadata or {}).get("category") for r in results):
+            return
+        by_category = pass_at_k_by_group(
+            (
+                ((r.model_name, (r.metadata or {}).get("category", "uncategorized")), r.case_name, r.passed)
+                for r in results
+            ),
+            self.pass_at_k
+        )
+        
+        category_table = Table(title="pass@k by Category")
+        category_table.add_column("Model", style="cyan")
+        category_table.add_column("Category")
+        for k in self.pass_at_k:
+            category_table.add_column(f"pass@{k}", justify="right")
+        for (model_name, category), rates in sorted(by_category.items()):
+            category_table.add_row(model_name, category, *(_format_rate(rates[k]) for k in self.pass_at_k))
+        
+        console.print(category_table)
+
+
+def _format_rate(rate: Optional[float]) -> str:
+    """Format a pass rate for the summary tables."""
+    return "-" if rate is None else f"{rate:.2f}"
\ No newline at end of file
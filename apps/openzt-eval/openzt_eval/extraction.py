@@ -0,0 +1,193 @@
+"""Extraction of code from model responses."""
+
+from typing import Any, Dict, List, Optional
+from dataclasses import dataclass, asdict
+import json
+import re
+import textwrap
+
+from .rust_syntax import parse_items, parse_function
+
+# Example: "```rust fibonacci\n...\n```"; as in CommonMark, the closing fence may be longer
+FENCE_PATTERN = re.compile(
+    r'^[ \t]*(`{3,}|~{3,})[ \t]*([^\n`]*)\n(.*?)^[ \t]*\1(?:(?<=`)`*|(?<=~)~*)[ \t]*$',
+    re.MULTILINE | re.DOTALL
+)
+
+RUST_LANGUAGES = ("rust", "rs")
+
+
+@dataclass
+class FencedBlock:
+    """A fenced code block found in a markdown response."""
+    info: str
+    code: str
+
+    @property
+    def language(self) -> Optional[str]:
+        """The language tag of the block, e.g. 'rust'."""
+        tokens = self._tokens()
+        return tokens[0].lower() if tokens else None
+
+    @property
+    def label(self) -> Optional[str]:
+        """The name following the language tag, e.g. 'fibonacci' in '```rust fibonacci'."""
+        tokens = self._tokens()
+        return tokens[-1] if len(tokens) > 1 else None
+
+    def _tokens(self) -> List[str]:
+        return [t for t in re.split(r'[\s:,]+', self.info.strip()) if t]
+
+
+@dataclass
+class Extraction:
+    """Code extracted from a model response, and how it was found."""
+    code: str
+    source: str  # "fenced_block", "unterminated_block" or "raw"
+    language: Optional[str] = None
+    blocks_found: int = 0
+    stripped_signature: Optional[str] = None
+
+    def to_metadata(self) -> Dict[str, Any]:
+        """Convert to a plain dict for scorer metadata."""
+        return asdict(self)
+
+
+def find_fenced_blocks(text: str) -> List[FencedBlock]:
+    """Find all fenced code blocks in a markdown text, in order of appearance."""
+    return [
+        FencedBlock(info=match.group(2).strip(), code=match.group(3).rstrip('\n'))
+        for match in FENCE_PATTERN.finditer(text)
+    ]
+
+
+def split_slot_response(response: str, slot_names: List[str]) -> Dict[str, str]:
+    """Split a model response into the code for each named substitution slot.
+
+    Two reply formats are understood:
+
+    - A JSON object mapping slot names to code, either bare or in a fenced block:
+      {"fibonacci": "...", "safe_divide": "..."}
+    - Fenced code blocks labelled with the slot name after the language tag:
+      ```rust fibonacci
+      ...
+      ```
+
+    Slots without an answer are left out of the result.
+    """
+    answers = _parse_json_slots(response, slot_names)
+    if answers:
+        return answers
+
+    answers = {}
+    for block in find_fenced_blocks(response):
+        if block.label in slot_names and block.label not in answers:
+            answers[block.label] = block.code
+    return answers
+
+
+def _parse_json_slots(response: str, slot_names: List[str]) -> Dict[str, str]:
+    """Parse a structured JSON reply, returning an empty dict if there is none."""
+    candidates = [response.strip()]
+    candidates.extend(b.code for b in find_fenced_blocks(response) if b.language == "json")
+
+    for candidate in candidates:
+        if not candidate.startswith('{'):
+            continue
+        try:
+            data = json.loads(candidate)
+        except json.JSONDecodeError:
+            continue
+        if isinstance(data, dict):
+            return {
+                name: code for name, code in data.items()
+                if name in slot_names and isinstance(code, str)
+            }
+
+    return {}
+
+
+def extract_code(response: str, function_name: Optional[str] = None) -> Extraction:
+    """Extract the Rust code from a model response.
+
+    Chat models usually wrap code in fenced blocks and surround it with prose.
+    Rust-tagged blocks are preferred over untagged ones, and blocks that define
+    function_name over those that don't; among equal candidates the longest
+    block wins. Responses without any fence are returned unchanged (stripped).
+
+    If function_name is given and the chosen code is a complete definition of
+    that function, the duplicated signature is stripped and only the body is
+    kept, since the substitution point already sits inside the function.
+    """
+    blocks = find_fenced_blocks(response)
+
+    if blocks:
+        block = _select_block(blocks, function_name)
+        extraction = Extraction(
+            code=block.code,
+            source="fenced_block",
+            language=block.language,
+            blocks_found=len(blocks)
+        )
+    else:
+        # A response cut off mid-block has an opening fence but no closing one
+        opening = re.search(r'^[ \t]*(`{3,}|~{3,})[ \t]*([^\n`]*)\n', response, re.MULTILINE)
+        if opening:
+            extraction = Extraction(
+                code=response[opening.end():].rstrip(),
+                source="unterminated_block",
+                language=FencedBlock(info=opening.group(2), code="").language
+            )
+        else:
+            extraction = Extraction(code=response.strip(), source="raw")
+
+    if function_name:
+        body = strip_function_signature(extraction.code, function_name)
+        if body is not None:
+            signature, extraction.code = body
+            extraction.stripped_signature = signature
+
+    return extraction
+
+
+def strip_function_signature(code: str, function_name: str) -> Optional[tuple]:
+    """Split a complete function definition into its signature and body.
+
+    Returns (signature, body) if code consists of exactly one definition of
+    function_name (optionally preceded by doc comments and attributes), or
+    None otherwise.
+    """
+    item = parse_function(code)
+    if item is None or item.name != function_name:
+        return None
+
+    body = textwrap.dedent(code[item.body_start + 1:item.end - 1].strip('\n')).strip()
+    return item.signature, body
+
+
+def enclosing_function(source: str, offset: int) -> Optional[str]:
+    """Name of the innermost function whose body contains offset, if any."""
+    name = None
+    innermost = -1
+    for item in parse_items(source):
+        if item.kind == "fn" and item.has_body and item.body_start < offset < item.end:
+            if item.body_start > innermost:
+                name, innermost = item.name, item.body_start
+    return name
+
+
+def _select_block(blocks: List[FencedBlock], function_name: Optional[str]) -> FencedBlock:
+    """Pick the block most likely to hold the answer."""
+    def rank(block: FencedBlock):
+        if block.language in RUST_LANGUAGES:
+            language_rank = 2
+        elif block.language is None:
+            language_rank = 1
+        else:
+            language_rank = 0
+        defines_function = bool(function_name) and any(
+            item.kind == "fn" and item.name == function_name for item in parse_items(block.code)
+        )
+        return (language_rank, defines_function, len(block.code))
+
+    return max(blocks, key=rank)
@@ -0,0 +1,58 @@
+"""Aggregate metrics over repeated samples."""
+
+from typing import Dict, Hashable, Iterable, List, Optional, Tuple
+
+
+def pass_at_k(n: int, c: int, k: int) -> float:
+    """Unbiased estimate of pass@k from n samples of which c passed.
+
+    This is the estimator from the Codex paper (Chen et al., 2021):
+    1 - C(n - c, k) / C(n, k), the probability that at least one of k samples
+    drawn without replacement from the n passes. It is computed as a running
+    product, since the binomial coefficients overflow floats for large n.
+
+    Args:
+        n: Number of samples generated
+        c: Number of those samples that passed
+        k: Number of attempts the metric allows, at most n
+
+    Returns:
+        The estimate, between 0.0 and 1.0
+    """
+    if not 0 < k <= n:
+        raise ValueError(f"k must be between 1 and n ({n}), got {k}")
+    if n - c < k:
+        return 1.0
+    estimate = 1.0
+    for i in range(n - c + 1, n + 1):
+        estimate *= 1.0 - k / i
+    return 1.0 - estimate
+
+
+def pass_at_k_by_group(outcomes: Iterable[Tuple[Hashable, str, bool]],
+                       ks: List[int]) -> Dict[Hashable, Dict[int, Optional[float]]]:
+    """Average pass@k over the cases of each group.
+
+    Args:
+        outcomes: (group, case, passed) for every sample; groups can be any hashable,
+            e.g. (model, category)
+        ks: The k values to report
+
+    Returns:
+        Mapping from group to {k: mean pass@k}; None where some case of the group
+        has fewer than k samples
+    """
+    samples: Dict[Hashable, Dict[str, List[bool]]] = {}
+    for group, case, passed in outcomes:
+        samples.setdefault(group, {}).setdefault(case, []).append(passed)
+
+    summary = {}
+    for group, cases in samples.items():
+        summary[group] = {}
+        for k in ks:
+            if any(len(results) < k for results in cases.values()):
+                summary[group][k] = None
+                continue
+            estimates = [pass_at_k(len(results), sum(results), k) for results in cases.values()]
+            summary[group][k] = sum(estimates) / len(estimates)
+    return summary
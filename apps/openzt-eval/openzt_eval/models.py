@@ -3,8 +3,13 @@
 from enum import Enum
 from typing import Optional, Dict, Any, List, Union
 from dataclasses import dataclass
+from datetime import datetime
+from pathlib import Path
+import hashlib
+import json
 import logging
 import os
+import threading
 
 try:
     from lmstudio import Client as LMStudioClient
@@ -29,6 +34,7 @@ class ModelType(Enum):
     ANTHROPIC = "anthropic"
     GEMINI = "gemini"
     CUSTOM = "custom"
+    REPLAY = "replay"
 
 
 @dataclass
@@ -40,6 +46,8 @@ class ModelConfig:
     api_key: Optional[str] = None
     model_id: Optional[str] = None
     parameters: Optional[Dict[str, Any]] = None
+    # For REPLAY models: the live model that records responses missing from the cassette
+    record: Optional["ModelConfig"] = None
     
     def __post_init__(self):
         if self.parameters is None:
@@ -55,6 +63,17 @@ class BaseModel:
     async def generate(self, prompt: str, **kwargs) -> str:
         """Generate a response from the model."""
         raise NotImplementedError
+    
+    async def chat(self, messages: List[Dict[str, str]], **kwargs) -> str:
+        """Generate the next assistant turn of a conversation.
+        
+        Messages are {"role": "user" | "assistant", "content": ...} dicts. Models
+        without a chat API get the conversation as a single transcript prompt.
+        """
+        transcript = "\n\n".join(
+            f"{message['role'].capitalize()}: {message['content']}" for message in messages
+        )
+        return await self.generate(f"{transcript}\n\nAssistant:", **kwargs)
 
 
 class LocalModel(BaseModel):
@@ -139,6 +158,10 @@ class RemoteModel(BaseModel):
     
     async def generate(self, prompt: str, **kwargs) -> str:
         """Generate response using OpenAI-compatible API."""
+        return await self.chat([{"role": "user", "content": prompt}], **kwargs)
+    
+    async def chat(self, messages: List[Dict[str, str]], **kwargs) -> str:
+        """Generate the next assistant turn using the chat completions API."""
         try:
             # Merge default parameters with kwargs
             params = {**self.config.parameters, **kwargs}
@@ -146,9 +169,7 @@ class RemoteModel(BaseModel):
             # Use chat completions API
             response = await self.client.chat.completions.create(
                 model=self.model_id,
-                messages=[
-                    {"role": "user", "content": prompt}
-                ],
+                messages=messages,
                 max_tokens=params.get("max_tokens", 1000),
                 temperature=params.get("temperature", 0.7),
             )
@@ -159,6 +180,122 @@ class RemoteModel(BaseModel):
             raise
 
 
+class CassetteMiss(LookupError):
+    """A replay-only model was asked for a response its cassette doesn't have."""
+
+
+class ReplayModel(BaseModel):
+    """Model that replays responses recorded in a cassette file.
+    
+    The cassette (config.endpoint) is a JSON Lines file with one request per
+    line: the model name, the prompt or conversation, the parameters, and the
+    response. A request is answered from the cassette when it was recorded
+    before; otherwise it goes to the live model in config.record and its
+    response is appended, so the next run replays it. Without config.record,
+    unrecorded requests raise CassetteMiss and nothing touches the network.
+    
+    Identical requests (several samples of one case) are recorded separately
+    and replayed in the order they were recorded.
+    """
+    
+    def __init__(self, config: ModelConfig):
+        super().__init__(config)
+        if not config.endpoint:
+            raise ValueError(f"Replay model {config.name} needs a cassette file as its endpoint")
+        
+        self.cassette = Path(config.endpoint)
+        self.recorded: Dict[str, List[str]] = {}
+        self.replayed = 0
+        self._calls: Dict[str, int] = {}
+        self._live: Optional[BaseModel] = None
+        self._lock = threading.Lock()
+        
+        if self.cassette.exists():
+            with open(self.cassette, encoding="utf-8") as f:
+                for line_number, line in enumerate(f, 1):
+                    if not line.strip():
+                        continue
+                    try:
+                        entry = json.loads(line)
+                        key = self._key(entry["model"], entry["request"], entry["parameters"])
+                    except (ValueError, KeyError) as e:
+                        raise ValueError(f"Invalid cassette entry at {self.cassette}:{line_number}: {e}") from e
+                    self.recorded.setdefault(key, []).append(entry["response"])
+        elif config.record is None:
+            raise FileNotFoundError(f"Cassette {self.cassette} does not exist and there is no model to record from")
+        
+        mode = f"recording misses from {config.record.name}" if config.record else "replay only"
+        logger.info(f"Loaded {sum(map(len, self.recorded.values()))} recorded responses "
+                    f"from {self.cassette} ({mode})")
+    
+    async def generate(self, prompt: str, **kwargs) -> str:
+        """Replay (or record) the response to a prompt."""
+        return await self._respond({"prompt": prompt}, kwargs)
+    
+    async def chat(self, messages: List[Dict[str, str]], **kwargs) -> str:
+        """Replay (or record) the next assistant turn of a conversation."""
+        return await self._respond({"messages": messages}, kwargs)
+    
+    async def _respond(self, request: Dict[str, Any], kwargs: Dict[str, Any]) -> str:
+        """Look a request up in the cassette, falling back to the live model."""
+        parameters = {**self.config.parameters, **kwargs}
+        key = self._key(self.config.name, request, parameters)
+        with self._lock:
+            call = self._calls.get(key, 0)
+            self._calls[key] = call + 1
+            responses = self.recorded.get(key, [])
+            if call < len(responses):
+                self.replayed += 1
+                return responses[call]
+        
+        if self.config.record is None:
+            raise CassetteMiss(f"No recorded response for model {self.config.name} "
+                               f"(call {call + 1} of this request) in {self.cassette}")
+        
+        live = self._live_model()
+        if "prompt" in request:
+            response = await live.generate(request["prompt"], **kwargs)
+        else:
+            response = await live.chat(request["messages"], **kwargs)
+        
+        entry = {
+            "model": self.config.name,
+            "request": request,
+            "parameters": parameters,
+            "response": response,
+            "recorded_at": datetime.now().isoformat(),
+        }
+        with self._lock:
+            self.recorded.setdefault(key, []).append(response)
+            self.cassette.parent.mkdir(parents=True, exist_ok=True)
+            with open(self.cassette, "a", encoding="utf-8") as f:
+                f.write(json.dumps(entry) + "\n")
+        return response
+    
+    def _live_model(self) -> BaseModel:
+        """The model to record from, created on the first miss so replays need no endpoint."""
+        with self._lock:
+            if self._live is None:
+                self._live = create_model(self.config.record)
+            return self._live
+    
+    @staticmethod
+    def _key(model: str, request: Dict[str, Any], parameters: Dict[str, Any]) -> str:
+        """Identify a request by everything that determines its response."""
+        payload = json.dumps([model, request, parameters], sort_keys=True)
+        return hashlib.sha256(payload.encode("utf-8")).hexdigest()
+
+
+def create_model(config: ModelConfig) -> BaseModel:
+    """Create the model implementation for a configuration."""
+    if config.type == ModelType.LOCAL:
+        return LocalModel(config)
+    if config.type == ModelType.REPLAY:
+        return ReplayModel(config)
+    # All remote models use the RemoteModel class with OpenAI-compatible API
+    return RemoteModel(config)
+
+
 class ModelLoader:
     """Loads and manages models."""
     
@@ -171,13 +308,7 @@ class ModelLoader:
             logger.info(f"Model {config.name} already loaded")
             return self.models[config.name]
         
-        # Create appropriate model instance
-        if config.type == ModelType.LOCAL:
-            model = LocalModel(config)
-        else:
-            # All remote models use the RemoteModel class with OpenAI-compatible API
-            model = RemoteModel(config)
-        
+        model = create_model(config)
         self.models[config.name] = model
         logger.info(f"Loaded model {config.name} (type: {config.type.value})")
         return model
@@ -0,0 +1,263 @@
+"""Unified diffs and whole-file replacements as model answers."""
+
+from typing import Any, Dict, List, Optional, Tuple
+from dataclasses import dataclass, field
+from pathlib import Path
+import re
+
+from .extraction import find_fenced_blocks
+
+DIFF_LANGUAGES = ("diff", "patch", "udiff")
+
+# Example: "@@ -12,7 +12,8 @@ fn main() {"
+HUNK_HEADER = re.compile(r'^@@ -(\d+)(?:,\d+)? \+\d+(?:,\d+)? @@')
+
+# A fenced block labelled with a path, e.g. "```rust src/main.rs", replaces that file
+FILE_LABEL = re.compile(r'^[\w.-]+(/[\w.-]+)*\.\w+$|/')
+
+
+class PatchError(Exception):
+    """An answer that doesn't apply to the checkout."""
+
+
+@dataclass
+class Hunk:
+    """One hunk of a unified diff. Its line counts are recomputed from the body."""
+    old_start: int  # 1-based line in the original file, as stated in the header
+    header: str
+    lines: List[str] = field(default_factory=list)  # each starting with ' ', '-' or '+'
+
+    @property
+    def old_lines(self) -> List[str]:
+        return [line[1:] for line in self.lines if line[0] in ' -']
+
+    @property
+    def new_lines(self) -> List[str]:
+        return [line[1:] for line in self.lines if line[0] in ' +']
+
+    def context(self) -> Tuple[int, int]:
+        """Number of context lines before the first and after the last change."""
+        changes = [i for i, line in enumerate(self.lines) if line[0] != ' ']
+        if not changes:
+            return len(self.lines), 0
+        return changes[0], len(self.lines) - 1 - changes[-1]
+
+
+@dataclass
+class FilePatch:
+    """The hunks of a diff for one file."""
+    old_path: Optional[str]  # None for a new file
+    new_path: Optional[str]  # None for a deleted file
+    hunks: List[Hunk] = field(default_factory=list)
+
+    @property
+    def path(self) -> str:
+        return self.new_path or self.old_path
+
+
+@dataclass
+class PatchAnswer:
+    """A model's answer to a patch case: a unified diff, or whole files by path."""
+    patches: List[FilePatch] = field(default_factory=list)
+    files: Dict[str, str] = field(default_factory=dict)
+
+    @property
+    def kind(self) -> str:
+        return "diff" if self.patches else "files"
+
+
+def parse_patch_answer(response: str) -> PatchAnswer:
+    """Find the diff or the file replacements in a model response.
+
+    A diff is taken from fenced blocks tagged diff or patch, or from the raw
+    response if it has file headers. Otherwise fenced blocks labelled with a
+    file path (```rust src/main.rs) replace those files as a whole.
+
+    Raises:
+        PatchError: If the response has neither
+    """
+    blocks = find_fenced_blocks(response)
+    diff_blocks = [b.code for b in blocks if b.language in DIFF_LANGUAGES]
+    if not diff_blocks and re.search(r'^--- .*\n\+\+\+ ', response, re.MULTILINE):
+        diff_blocks = [response]
+    if diff_blocks:
+        patches = parse_unified_diff("\n".join(diff_blocks))
+        if not patches:
+            raise PatchError("The diff has no file headers (--- a/path, +++ b/path) or no hunks")
+        return PatchAnswer(patches=patches)
+
+    files = {}
+    for block in blocks:
+        if block.label and FILE_LABEL.search(block.label) and block.label not in files:
+            files[block.label] = block.code
+    if not files:
+        raise PatchError("No unified diff or whole files labelled with their path found in the answer")
+    return PatchAnswer(files=files)
+
+
+def parse_unified_diff(text: str) -> List[FilePatch]:
+    """Parse the files and hunks of a unified diff, tolerating wrong hunk line counts."""
+    patches = []
+    hunk = None
+    lines = text.splitlines()
+    for i, line in enumerate(lines):
+        if line.startswith("--- ") and i + 1 < len(lines) and lines[i + 1].startswith("+++ "):
+            patches.append(FilePatch(_diff_path(line[4:]), _diff_path(lines[i + 1][4:])))
+            hunk = None
+        elif line.startswith("+++ ") and hunk is None:
+            continue
+        elif patches and HUNK_HEADER.match(line):
+            hunk = Hunk(old_start=int(HUNK_HEADER.match(line).group(1)), header=line)
+            patches[-1].hunks.append(hunk)
+        elif hunk is not None and line[:1] in (' ', '-', '+'):
+            hunk.lines.append(line)
+        elif hunk is not None and line == "":
+            # Editors and models drop the space that marks an empty context line
+            hunk.lines.append(" ")
+        elif line.startswith("\\"):
+            continue  # "\ No newline at end of file"
+        else:
+            hunk = None
+
+    for patch in patches:
+        for hunk in patch.hunks:
+            # A blank line after the last hunk separates it from what follows
+            while hunk.lines and hunk.lines[-1] == " ":
+                hunk.lines.pop()
+    return [patch for patch in patches if patch.hunks or patch.new_path is None]
+
+
+def apply_hunks(content: str, hunks: List[Hunk], fuzz: int = 2) -> Tuple[str, List[Dict[str, int]]]:
+    """Apply the hunks of one file, like patch(1) with --fuzz.
+
+    Each hunk is looked for nearest to the line its header gives, after the
+    previous hunk. Lines are compared without trailing whitespace. If a hunk
+    isn't found, up to `fuzz` lines of context at either end are ignored.
+
+    Returns:
+        The patched content, and the offset and fuzz each hunk applied with
+
+    Raises:
+        PatchError: If a hunk doesn't apply anywhere
+    """
+    lines = content.splitlines()
+    applied = []
+    start = 0  # hunks apply in order and don't overlap
+    shift = 0  # lines added so far, minus lines removed
+    for number, hunk in enumerate(hunks, 1):
+        leading, trailing = hunk.context()
+        old, new = hunk.old_lines, hunk.new_lines
+        position = None
+        tried = set()
+        for level in range(fuzz + 1):
+            lead, trail = min(level, leading), min(level, trailing)
+            if (lead, trail) in tried:
+                continue
+            tried.add((lead, trail))
+            expected = max(hunk.old_start - 1, 0) + shift + lead
+            position = _find(lines, old[lead:len(old) - trail], expected, start)
+            if position is not None:
+                break
+        if position is None:
+            raise PatchError(f"Hunk {number} ({hunk.header}) does not apply")
+
+        old, new = old[lead:len(old) - trail], new[lead:len(new) - trail]
+        lines[position:position + len(old)] = new
+        applied.append({"offset": position - expected, "fuzz": level})
+        start = position + len(new)
+        shift += len(new) - len(old)
+
+    patched = "\n".join(lines)
+    if lines and (content.endswith("\n") or not content):
+        patched += "\n"
+    return patched, applied
+
+
+def apply_patch_answer(repo_path: Path, answer: PatchAnswer, fuzz: int = 2) -> Dict[str, Dict[str, Any]]:
+    """Apply an answer to a checkout, changing nothing unless all of it applies.
+
+    Args:
+        repo_path: The checkout
+        answer: The parsed answer
+        fuzz: Context lines a hunk may ignore at either end
+
+    Returns:
+        What happened to each file: its "action", and the hunks of a diff
+
+    Raises:
+        PatchError: If a path leaves the checkout, a file is missing or a hunk doesn't apply
+    """
+    writes: Dict[Path, Optional[str]] = {}
+    report = {}
+    for path, content in answer.files.items():
+        target = _resolve(repo_path, path)
+        writes[target] = content if content.endswith("\n") else content + "\n"
+        report[path] = {"action": "replaced" if target.exists() else "created"}
+
+    for patch in answer.patches:
+        target = _resolve(repo_path, patch.path)
+        if patch.old_path is None:
+            original = ""
+            if target.exists():
+                raise PatchError(f"{patch.path}: the diff creates a file that already exists")
+        elif target in writes:
+            original = writes[target]
+            if original is None:
+                raise PatchError(f"{patch.path}: the diff changes a file it deletes")
+        elif target.is_file():
+            original = target.read_text(encoding='utf-8')
+        else:
+            raise PatchError(f"{patch.path}: no such file")
+
+        if patch.new_path is None:
+            writes[target] = None
+            report[patch.path] = {"action": "deleted"}
+            continue
+        try:
+            writes[target], hunks = apply_hunks(original, patch.hunks, fuzz)
+        except PatchError as e:
+            raise PatchError(f"{patch.path}: {e}") from e
+        report[patch.path] = {"action": "created" if patch.old_path is None else "modified", "hunks": hunks}
+
+    for target, content in writes.items():
+        if content is None:
+            target.unlink()
+        else:
+            target.parent.mkdir(parents=True, exist_ok=True)
+            target.write_text(content, encoding='utf-8')
+    return report
+
+
+def _find(lines: List[str], block: List[str], expected: int, start: int) -> Optional[int]:
+    """The position of block in lines at or after start that is nearest to expected."""
+    last = len(lines) - len(block)
+    if last < start:
+        return None
+    if not block:
+        return min(max(expected, start), len(lines))
+    wanted = [line.rstrip() for line in block]
+    for distance in range(max(expected - start, last - expected) + 1):
+        for position in (expected - distance, expected + distance):
+            if start <= position <= last and all(
+                lines[position + i].rstrip() == wanted[i] for i in range(len(wanted))
+            ):
+                return position
+    return None
+
+
+def _diff_path(header: str) -> Optional[str]:
+    """The path in a --- or +++ line, without timestamp or a/ b/ prefix; None for /dev/null."""
+    path = header.split("\t")[0].strip()
+    if path == "/dev/null":
+        return None
+    if path.startswith(("a/", "b/")):
+        path = path[2:]
+    return path
+
+
+def _resolve(repo_path: Path, path: str) -> Path:
+    """The file a path in an answer refers to, which must be inside the checkout."""
+    relative = Path(path)
+    if relative.is_absolute() or ".." in relative.parts or not relative.parts:
+        raise PatchError(f"{path}: path is outside the crate")
+    return repo_path / relative
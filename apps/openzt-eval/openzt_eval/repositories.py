@@ -0,0 +1,164 @@
+"""Cached git mirrors of the repositories under test, checked out as worktrees."""
+
+from typing import Dict, Iterator, Optional, Tuple
+from contextlib import contextmanager
+from pathlib import Path
+import hashlib
+import logging
+import os
+import re
+import threading
+
+import git
+
+logger = logging.getLogger(__name__)
+
+FULL_SHA = re.compile(r"^[0-9a-f]{40}$")
+
+
+class CheckoutError(Exception):
+    """The requested revision of a repository could not be checked out."""
+
+
+def default_cache_dir() -> Path:
+    """Where mirrors are kept unless a directory is given: $XDG_CACHE_HOME/openzt-eval/git."""
+    cache_home = os.environ.get("XDG_CACHE_HOME") or Path.home() / ".cache"
+    return Path(cache_home) / "openzt-eval" / "git"
+
+
+class GitMirrorCache:
+    """
+    Bare mirrors of remote repositories, kept across runs.
+
+    Each repository is cloned once with `git clone --mirror`; every evaluation
+    then gets its own `git worktree` of the mirror, detached at a commit.
+    Branch and tag names are resolved to a commit SHA once per cache, so all
+    evaluations of a run see the same revision even if a branch moves, and the
+    mirror is fetched at most once per run unless a pinned commit is missing.
+    """
+
+    def __init__(self, root: Optional[Path] = None):
+        from cargo_orchestrator.cache import KeyedLock
+
+        self.root = Path(root) if root else default_cache_dir()
+        self._locks = KeyedLock(self.root)
+        self._resolved: Dict[Tuple[str, str], str] = {}
+        self._fetched = set()
+        self._opened = set()
+        self._resolve_guard = threading.Lock()
+
+    def mirror_path(self, url: str) -> Path:
+        """Where the mirror of url is (or will be) kept."""
+        name = re.sub(r"[^A-Za-z0-9_.-]", "_", url.rstrip("/").rsplit("/", 1)[-1])
+        if not name.endswith(".git"):
+            name += ".git"
+        return self.root / f"{hashlib.sha1(url.encode('utf-8')).hexdigest()[:16]}-{name}"
+
+    def resolve(self, url: str, revision: Optional[str] = None) -> str:
+        """
+        Resolve a branch, tag or commit of url to a full commit SHA.
+
+        Args:
+            url: The repository's URL (or local path)
+            revision: Branch, tag or commit; the remote's default branch if None
+
+        Returns:
+            The commit SHA
+
+        Raises:
+            CheckoutError: If the repository can't be mirrored or has no such revision
+        """
+        revision = revision or "HEAD"
+        with self._resolve_guard:
+            if (url, revision) in self._resolved:
+                return self._resolved[(url, revision)]
+
+        with self._locks.hold(self.mirror_path(url).name):
+            mirror = self._open_mirror(url)
+            sha = None
+            # A commit that is already mirrored never changes; anything else may have moved
+            if FULL_SHA.match(revision):
+                sha = self._rev_parse(mirror, revision)
+            if sha is None and url not in self._fetched:
+                self._fetch(mirror, url)
+            if sha is None:
+                sha = self._rev_parse(mirror, revision)
+        if sha is None:
+            raise CheckoutError(f"No branch, tag or commit {revision!r} in {url}")
+
+        with self._resolve_guard:
+            sha = self._resolved.setdefault((url, revision), sha)
+        logger.info(f"Resolved {url} {revision} to {sha}")
+        return sha
+
+    @contextmanager
+    def worktree(self, url: str, commit: str, destination: Path) -> Iterator[Path]:
+        """
+        Check out a commit of url at destination for as long as the context lasts.
+
+        Args:
+            url: The repository's URL, already mirrored by resolve()
+            commit: The commit SHA to check out
+            destination: Where to put the worktree; must not exist yet
+
+        Yields:
+            The worktree directory
+
+        Raises:
+            CheckoutError: If the worktree can't be created
+        """
+        lock_name = self.mirror_path(url).name
+        with self._locks.hold(lock_name):
+            mirror = self._open_mirror(url)
+            try:
+                mirror.git.worktree("add", "--detach", str(destination), commit)
+            except git.exc.GitCommandError as e:
+                raise CheckoutError(f"Failed to check out {commit} of {url}: {e}") from e
+        try:
+            yield destination
+        finally:
+            with self._locks.hold(lock_name):
+                try:
+                    mirror.git.worktree("remove", "--force", str(destination))
+                except git.exc.GitCommandError as e:
+                    # E.g. the directory is already gone; drop its bookkeeping instead
+                    logger.debug(f"Removing worktree {destination} failed: {e}")
+                    mirror.git.worktree("prune")
+
+    def _open_mirror(self, url: str) -> git.Repo:
+        """Open the mirror of url, cloning it first if needed. The caller holds its lock."""
+        path = self.mirror_path(url)
+        if not (path / "HEAD").is_file():
+            logger.info(f"Mirroring {url} to {path}")
+            self.root.mkdir(parents=True, exist_ok=True)
+            try:
+                git.Repo.clone_from(url, path, mirror=True)
+            except git.exc.GitCommandError as e:
+                raise CheckoutError(f"Failed to mirror {url}: {e}") from e
+            self._fetched.add(url)
+            self._opened.add(url)
+            return git.Repo(path)
+
+        mirror = git.Repo(path)
+        if url not in self._opened:
+            # Worktrees of an interrupted run are left registered
+            mirror.git.worktree("prune")
+            self._opened.add(url)
+        return mirror
+
+    def _fetch(self, mirror: git.Repo, url: str):
+        """Update a mirror from its remote."""
+        logger.info(f"Fetching {url}")
+        try:
+            mirror.git.fetch("origin", prune=True)
+        except git.exc.GitCommandError as e:
+            raise CheckoutError(f"Failed to fetch {url}: {e}") from e
+        self._fetched.add(url)
+
+    @staticmethod
+    def _rev_parse(mirror: git.Repo, revision: str) -> Optional[str]:
+        """The commit a revision names in the mirror, or None."""
+        try:
+            return mirror.git.rev_parse("--verify", "--quiet", f"{revision}^{{commit}}") or None
+        except git.exc.GitCommandError:
+            return None
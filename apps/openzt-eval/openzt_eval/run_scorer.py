@@ -0,0 +1,216 @@
+"""Scorer for complete Rust programs, judged by their output."""
+
+from typing import Any, List, Optional
+from contextlib import ExitStack
+from pathlib import Path
+import difflib
+import logging
+import tempfile
+
+from .extraction import extract_code
+from .repositories import CheckoutError
+from .scorers import RustBuildScorer, ScorerResult, feedback_block
+from .test_cases import CaseExpectations, RustRunTestCase
+
+logger = logging.getLogger(__name__)
+
+
+class RustRunScorer(RustBuildScorer):
+    """Scorer that builds and runs a complete Rust program and compares its output.
+    
+    The expected JSON holds RustRunTestCase data:
+    {
+        "local_path": "test_projects/rust_run_test",
+        "file_path": "src/main.rs",
+        "expected_stdout": "Sum of numbers: 15\\n5! = 120\\n",
+        "args": ["--verbose"],
+        "stdin": "optional input",
+        "expected_exit_code": 0
+    }
+    
+    As for RustBuildScorer, repo_url and tag_or_branch can be given instead
+    of local_path. A "time_limit" in the case's metadata limits how long
+    the program may run, unless the test case sets its own timeout.
+    """
+    
+    test_case_class = RustRunTestCase
+    
+    def __init__(self,
+                 run_timeout: float = 10.0,
+                 partial_credit: bool = True,
+                 **kwargs):
+        """Initialize the Rust run scorer.
+        
+        Args:
+            run_timeout: Seconds the program may run before it is killed, unless
+                the test case sets its own timeout
+            partial_credit: Whether mismatching output scores by line similarity
+                instead of 0
+            **kwargs: Passed on to RustBuildScorer; the program is neither linted
+                nor tested, and memory_limit_mb and cpu_time_limit also apply to it
+        """
+        super().__init__(use_clippy=False, run_tests=False, **kwargs)
+        self.name = "rust_run"
+        self.run_timeout = run_timeout
+        self.partial_credit = partial_credit
+    
+    def repair_feedback(self, result: ScorerResult) -> Optional[str]:
+        """Send back the reason with the compiler output, or how the program's output differs."""
+        metadata = result.metadata or {}
+        parts = [f"The program did not pass: {result.reason}."]
+        if metadata.get("diagnostics"):
+            parts.append(feedback_block("Compiler output", "\n\n".join(metadata["diagnostics"])))
+        if metadata.get("output_diff"):
+            parts.append(feedback_block("Expected and actual output", metadata["output_diff"]))
+        if metadata.get("stderr"):
+            parts.append(feedback_block("Standard error", metadata["stderr"].rstrip()))
+        return "\n\n".join(parts)
+    
+    def _evaluate_with_test_case(self, test_case: RustRunTestCase, response: str,
+                                 expectations: Optional[CaseExpectations] = None) -> ScorerResult:
+        """Build and run the program with a specific test case."""
+        if test_case.timeout is None and expectations:
+            test_case.timeout = expectations.time_limit
+        with tempfile.TemporaryDirectory() as temp_dir, ExitStack() as stack:
+            temp_path = Path(temp_dir)
+            
+            try:
+                repo_path, revision = stack.enter_context(self._warm_checkout(test_case, temp_path))
+                
+                extraction = None
+                program = response
+                if self.extract_response:
+                    extracted = extract_code(response)
+                    program = extracted.code
+                    extraction = extracted.to_metadata()
+                
+                target_file = repo_path / test_case.file_path
+                target_file.parent.mkdir(parents=True, exist_ok=True)
+                target_file.write_text(program.rstrip() + "\n", encoding='utf-8')
+                
+                run_result = self._run_program(repo_path, test_case)
+                result = self._compare_output(run_result, test_case)
+                result.metadata.update(self._provenance(repo_path, revision))
+                if extraction:
+                    result.metadata["extraction"] = extraction
+                return result
+                
+            except CheckoutError:
+                raise
+            except Exception as e:
+                logger.error(f"Error during Rust run evaluation: {e}")
+                return ScorerResult(
+                    score=0.0,
+                    passed=False,
+                    reason=f"Evaluation failed: {str(e)}"
+                )
+    
+    def _run_program(self, repo_path: Path, test_case: RustRunTestCase) -> Any:
+        """Build and run the binary and return the result."""
+        try:
+            builder = self._create_builder(repo_path)
+            result = builder.run(
+                bin=test_case.bin,
+                args=test_case.args,
+                stdin=test_case.stdin,
+                timeout=test_case.timeout or self.run_timeout
+            )
+            
+            logger.info(f"Cargo run completed: success={result.success}, "
+                       f"exit_code={result.exit_code}, timed_out={result.timed_out}")
+            
+            return result
+        except ImportError:
+            raise RuntimeError("cargo-orchestrator library is required for RustRunScorer")
+    
+    def _compare_output(self, run_result: Any, test_case: RustRunTestCase) -> ScorerResult:
+        """Score the program's output against the expected transcript."""
+        from cargo_orchestrator.parser import MessageLevel
+        
+        build_errors = sum(1 for msg in run_result.build.messages if msg.level == MessageLevel.ERROR)
+        metadata = {
+            "build_success": run_result.build.success,
+            "build_status": run_result.build.status.value,
+            "build_errors": build_errors,
+            "exit_code": run_result.exit_code,
+            "expected_exit_code": test_case.expected_exit_code,
+            "timed_out": run_result.timed_out,
+            "run_duration": run_result.duration,
+            "test_case": {
+                "repo_url": test_case.repo_url,
+                "tag_or_branch": test_case.tag_or_branch,
+                "local_path": test_case.local_path,
+                "file_path": test_case.file_path,
+                "description": test_case.description
+            }
+        }
+        
+        if not run_result.build.success:
+            metadata["diagnostics"] = [(msg.rendered or f"{msg.level.value}: {msg.message}").rstrip()
+                                       for msg in run_result.build.messages]
+            if run_result.build.timed_out:
+                reason = f"Build timed out after {self.timeout} seconds"
+            else:
+                reason = f"Build failed with {build_errors} errors"
+            return ScorerResult(
+                score=0.0,
+                passed=False,
+                reason=reason,
+                metadata=metadata
+            )
+        
+        if run_result.exit_code is None:
+            return ScorerResult(
+                score=0.0,
+                passed=False,
+                reason=run_result.error or "Program did not run",
+                metadata=metadata
+            )
+        
+        # Trailing whitespace and line endings are not part of the transcript
+        actual_lines = normalize_output(run_result.stdout)
+        expected_lines = normalize_output(test_case.expected_stdout)
+        output_matches = actual_lines == expected_lines
+        exit_code_matches = run_result.exit_code == test_case.expected_exit_code
+        
+        similarity = difflib.SequenceMatcher(None, expected_lines, actual_lines).ratio()
+        if output_matches:
+            score = 1.0
+        elif self.partial_credit:
+            score = similarity
+        else:
+            score = 0.0
+        if not exit_code_matches:
+            score *= 0.5
+        
+        metadata.update({
+            "output_matches": output_matches,
+            "output_similarity": similarity,
+            "stdout": run_result.stdout,
+            "stderr": run_result.stderr,
+        })
+        if not output_matches:
+            metadata["output_diff"] = "\n".join(difflib.unified_diff(
+                expected_lines, actual_lines, "expected", "actual", lineterm=""
+            ))
+        
+        reason_parts = []
+        if not output_matches:
+            reason_parts.append(f"Output differs from expected (similarity {similarity:.2f})")
+        if not exit_code_matches:
+            reason_parts.append(f"Exit code {run_result.exit_code}, expected {test_case.expected_exit_code}")
+        
+        return ScorerResult(
+            score=score,
+            passed=output_matches and exit_code_matches,
+            reason="; ".join(reason_parts) if reason_parts else "Program output matches",
+            metadata=metadata
+        )
+
+
+def normalize_output(output: str) -> List[str]:
+    """Split program output into lines, ignoring trailing whitespace and blank lines."""
+    lines = [line.rstrip() for line in output.replace("\r\n", "\n").split("\n")]
+    while lines and not lines[-1]:
+        lines.pop()
+    return lines
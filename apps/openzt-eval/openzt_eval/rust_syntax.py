@@ -0,0 +1,456 @@
+"""Lightweight Rust syntax parsing for locating and replacing items."""
+
+from typing import List, Optional, Tuple
+from dataclasses import dataclass, field
+import re
+import textwrap
+
+IDENT_PATTERN = re.compile(r'(?:r#)?[A-Za-z_][A-Za-z0-9_]*')
+NUMBER_PATTERN = re.compile(r'\d[\d_]*(?:\.\d[\d_]*)?(?:[eE][+-]?\d+)?[A-Za-z0-9_]*')
+CHAR_PATTERN = re.compile(r"b?'(?:\\(?:u\{[0-9a-fA-F_]+\}|x[0-9a-fA-F]{2}|.)|[^\\'\n])'")
+RAW_STRING_PATTERN = re.compile(r'b?r(#*)"')
+
+MULTI_CHAR_PUNCT = ("::", "->", "=>")
+OPEN_DELIMITERS = {"(": ")", "[": "]", "{": "}"}
+FN_QUALIFIERS = ("const", "async", "unsafe", "extern", "default")
+
+
+class SubstitutionError(ValueError):
+    """Raised when an answer cannot be substituted for a Rust item."""
+
+
+@dataclass
+class Token:
+    """A single Rust token with its byte range in the source."""
+    kind: str  # "ident", "punct", "literal", "lifetime" or "doc"
+    text: str
+    start: int
+    end: int
+
+
+@dataclass
+class RustItem:
+    """An item (function, impl, struct, ...) found in Rust source."""
+    kind: str
+    name: str
+    path: str
+    start: int  # including doc comments and attributes
+    signature_start: int
+    end: int
+    body_start: Optional[int] = None  # offset of the opening brace
+    signature: str = ""
+    signature_tokens: List[str] = field(default_factory=list)
+
+    @property
+    def has_body(self) -> bool:
+        return self.body_start is not None
+
+
+def tokenize(source: str) -> List[Token]:
+    """Split Rust source into tokens, dropping whitespace and ordinary comments."""
+    tokens = []
+    length = len(source)
+    i = 0
+
+    while i < length:
+        char = source[i]
+
+        if char.isspace():
+            i += 1
+            continue
+
+        if source.startswith('//', i):
+            end = source.find('\n', i)
+            end = length if end == -1 else end
+            text = source[i:end]
+            if (text.startswith('///') and not text.startswith('////')) or text.startswith('//!'):
+                tokens.append(Token("doc", text, i, end))
+            i = end
+            continue
+
+        if source.startswith('/*', i):
+            depth, j = 1, i + 2
+            while j < length and depth:
+                if source.startswith('/*', j):
+                    depth, j = depth + 1, j + 2
+                elif source.startswith('*/', j):
+                    depth, j = depth - 1, j + 2
+                else:
+                    j += 1
+            text = source[i:j]
+            if (text.startswith('/**') and not text.startswith('/***') and text != '/**/') or text.startswith('/*!'):
+                tokens.append(Token("doc", text, i, j))
+            i = j
+            continue
+
+        follows_ident = i > 0 and (source[i - 1].isalnum() or source[i - 1] == '_')
+
+        raw = RAW_STRING_PATTERN.match(source, i)
+        if raw and not follows_ident:
+            terminator = '"' + raw.group(1)
+            end = source.find(terminator, raw.end())
+            end = length if end == -1 else end + len(terminator)
+            tokens.append(Token("literal", source[i:end], i, end))
+            i = end
+            continue
+
+        if char == '"' or (source.startswith('b"', i) and not follows_ident):
+            j = i + (2 if char == 'b' else 1)
+            while j < length and source[j] != '"':
+                j += 2 if source[j] == '\\' else 1
+            end = min(j + 1, length)
+            tokens.append(Token("literal", source[i:end], i, end))
+            i = end
+            continue
+
+        if char == "'" or (source.startswith("b'", i) and not follows_ident):
+            literal = CHAR_PATTERN.match(source, i)
+            if literal:
+                tokens.append(Token("literal", literal.group(), i, literal.end()))
+                i = literal.end()
+                continue
+            lifetime = IDENT_PATTERN.match(source, i + 1)
+            if char == "'" and lifetime:
+                tokens.append(Token("lifetime", source[i:lifetime.end()], i, lifetime.end()))
+                i = lifetime.end()
+                continue
+
+        ident = IDENT_PATTERN.match(source, i)
+        if ident:
+            tokens.append(Token("ident", ident.group(), i, ident.end()))
+            i = ident.end()
+            continue
+
+        number = NUMBER_PATTERN.match(source, i)
+        if number:
+            tokens.append(Token("literal", number.group(), i, number.end()))
+            i = number.end()
+            continue
+
+        punct = next((p for p in MULTI_CHAR_PUNCT if source.startswith(p, i)), char)
+        tokens.append(Token("punct", punct, i, i + len(punct)))
+        i += len(punct)
+
+    return tokens
+
+
+def parse_items(source: str) -> List[RustItem]:
+    """Parse all items in a Rust source, including those nested in impls, traits and modules."""
+    return _ItemParser(source).parse()
+
+
+def parse_function(code: str) -> Optional[RustItem]:
+    """Parse code that consists of exactly one function definition, or return None."""
+    parser = _ItemParser(code)
+    items = parser.parse()
+    top_level = [item for item in items if "::" not in item.path]
+    if len(top_level) != 1 or top_level[0].kind != "fn" or not top_level[0].has_body:
+        return None
+    item = top_level[0]
+    if code[item.end:].strip() or parser.other_top_level:
+        return None
+    return item
+
+
+def find_item(source: str, path: str) -> RustItem:
+    """Find the item with the given path (e.g. 'Stack::pop'), raising LookupError if it isn't unique."""
+    matches = [item for item in parse_items(source) if item.path == path]
+    # Prefer functions, so "Stack" can't clash with "impl Stack" but "Stack::pop" is unambiguous
+    if len(matches) > 1:
+        matches = [item for item in matches if item.kind != "impl"]
+    if not matches:
+        raise LookupError(f"Item not found: {path}")
+    if len(matches) > 1:
+        raise LookupError(f"Item path is ambiguous: {path} ({len(matches)} matches)")
+    return matches[0]
+
+
+def signature_key(item: RustItem) -> str:
+    """Normalize a function signature to the parts that make up its public API.
+
+    Parameter patterns (names, mut bindings) are dropped, since changing them
+    doesn't change how the function can be called.
+    """
+    tokens = item.signature_tokens
+    try:
+        open_paren = tokens.index("(", tokens.index("fn"))
+    except ValueError:
+        return " ".join(tokens)
+
+    depth = 0
+    close_paren = len(tokens)
+    for i in range(open_paren, len(tokens)):
+        if tokens[i] in ("(", "[", "{", "<"):
+            depth += 1
+        elif tokens[i] in (")", "]", "}", ">"):
+            depth -= 1
+            if depth == 0:
+                close_paren = i
+                break
+
+    params, current, depth = [], [], 0
+    for token in tokens[open_paren + 1:close_paren]:
+        if token in ("(", "[", "<"):
+            depth += 1
+        elif token in (")", "]", ">"):
+            depth -= 1
+        if token == "," and depth == 0:
+            params.append(current)
+            current = []
+        else:
+            current.append(token)
+    if current:
+        params.append(current)
+
+    normalized = []
+    for param in params:
+        if param[:2] == ["mut", "self"]:
+            # A mutable binding of self by value
+            normalized.append(" ".join(param[1:]))
+        elif "self" in param and ":" not in param:
+            normalized.append(" ".join(param))
+        elif ":" in param:
+            normalized.append(" ".join(param[param.index(":") + 1:]))
+        else:
+            normalized.append(" ".join(param))
+
+    return " ".join(tokens[:open_paren] + ["(", ", ".join(normalized), ")"] + tokens[close_paren + 1:])
+
+
+def substitute_item(source: str, path: str, answer: str, replace: str = "auto") -> Tuple[str, str]:
+    """Substitute an answer for the function at path, returning (new_source, mode).
+
+    With replace="item" the answer must be a complete definition of the
+    function, which replaces the whole item. With replace="body" the answer
+    replaces only the function body; a complete definition is accepted too and
+    its body is used. With replace="auto" a complete definition replaces the
+    item and anything else is taken as the body. Whenever the answer contains
+    a complete definition, its public signature must match the original.
+    """
+    if replace not in ("auto", "item", "body"):
+        raise SubstitutionError(f"Unknown replace mode: {replace}")
+
+    try:
+        item = find_item(source, path)
+    except LookupError as e:
+        raise SubstitutionError(str(e))
+    if item.kind != "fn" or not item.has_body:
+        raise SubstitutionError(f"{path} is not a function with a body")
+
+    answer_item = parse_function(answer)
+    if answer_item and answer_item.name != item.name:
+        answer_item = None
+
+    if replace == "item" and answer_item is None:
+        raise SubstitutionError(f"Answer is not a complete definition of {path}")
+
+    if answer_item is not None and signature_key(answer_item) != signature_key(item):
+        raise SubstitutionError(
+            f"Signature of {path} changed: expected `{item.signature}`, got `{answer_item.signature}`"
+        )
+
+    if answer_item is not None and replace != "body":
+        # Keep the original doc comments and attributes unless the answer brings its own
+        start = item.start if answer_item.start < answer_item.signature_start else item.signature_start
+        replacement = answer[answer_item.start if start == item.start else answer_item.signature_start:answer_item.end]
+        return source[:start] + replacement + source[item.end:], "item"
+
+    if answer_item is not None:
+        body = answer[answer_item.body_start + 1:answer_item.end - 1]
+    else:
+        body = answer
+
+    line_start = source.rfind('\n', 0, item.signature_start) + 1
+    indent = re.match(r'[ \t]*', source[line_start:]).group()
+    body = textwrap.indent(textwrap.dedent(body.strip('\n')).strip(), indent + "    ")
+    return source[:item.body_start + 1] + "\n" + body + "\n" + indent + source[item.end - 1:], "body"
+
+
+class _ItemParser:
+    """Recursive item parser over a token list."""
+
+    def __init__(self, source: str):
+        self.source = source
+        self.tokens = tokenize(source)
+        self.closing = self._match_delimiters()
+        self.items: List[RustItem] = []
+        self.other_top_level = False
+
+    def parse(self) -> List[RustItem]:
+        self._parse_block(0, len(self.tokens), "")
+        return self.items
+
+    def _match_delimiters(self) -> List[int]:
+        """Map each opening delimiter to its closing token (or the end if unclosed)."""
+        closing = [-1] * len(self.tokens)
+        stack = []
+        for i, token in enumerate(self.tokens):
+            if token.kind != "punct":
+                continue
+            if token.text in OPEN_DELIMITERS:
+                stack.append(i)
+            elif token.text in OPEN_DELIMITERS.values():
+                if stack and OPEN_DELIMITERS[self.tokens[stack[-1]].text] == token.text:
+                    closing[stack.pop()] = i
+        for i in stack:
+            closing[i] = len(self.tokens)
+        return closing
+
+    def _text(self, i: int) -> Optional[str]:
+        return self.tokens[i].text if i < len(self.tokens) else None
+
+    def _end_offset(self, i: int) -> int:
+        """Source offset just after token i (or the end of the source)."""
+        return self.tokens[i].end if i < len(self.tokens) else len(self.source)
+
+    def _skip_to(self, i: int, end: int, stops: Tuple[str, ...]) -> int:
+        """Advance to the first stop token at this nesting level, jumping over groups."""
+        while i < end:
+            text = self.tokens[i].text
+            if self.tokens[i].kind == "punct" and text in stops:
+                return i
+            if self.tokens[i].kind == "punct" and text in OPEN_DELIMITERS:
+                i = self.closing[i]
+            i += 1
+        return end
+
+    def _parse_block(self, i: int, end: int, prefix: str):
+        while i < end:
+            i = self._parse_item(i, end, prefix)
+
+    def _parse_item(self, i: int, end: int, prefix: str) -> int:
+        tokens = self.tokens
+        first = i
+
+        # Doc comments and outer/inner attributes
+        while i < end:
+            if tokens[i].kind == "doc":
+                i += 1
+            elif tokens[i].text == "#":
+                j = i + 1
+                if self._text(j) == "!":
+                    j += 1
+                if self._text(j) != "[":
+                    break
+                i = self.closing[j] + 1
+            else:
+                break
+        if i >= end:
+            return end
+
+        signature_start = i
+        if tokens[i].text == "pub":
+            i += 1
+            if self._text(i) == "(":
+                i = self.closing[i] + 1
+
+        while i < end and tokens[i].text in FN_QUALIFIERS:
+            following = self._text(i + 1)
+            if tokens[i].text == "const" and following not in ("fn", "unsafe", "async", "extern"):
+                break
+            if tokens[i].text == "extern" and following == "crate":
+                break
+            i += 1
+            if tokens[i - 1].text == "extern" and i < end and tokens[i].kind == "literal":
+                i += 1
+
+        keyword = self._text(i)
+        name = self._text(i + 1) if i + 1 < end and tokens[i + 1].kind == "ident" else None
+
+        if keyword == "fn" and name:
+            stop = self._skip_to(i + 2, end, ("{", ";"))
+            body_start = tokens[stop].start if stop < end and tokens[stop].text == "{" else None
+            last = self.closing[stop] if body_start is not None else stop
+            self._add("fn", name, prefix, first, signature_start, i, stop, last, body_start)
+            return last + 1
+
+        if keyword in ("mod", "trait") and name:
+            stop = self._skip_to(i + 2, end, ("{", ";"))
+            if stop < end and tokens[stop].text == "{":
+                self._add(keyword, name, prefix, first, signature_start, i, stop, self.closing[stop], tokens[stop].start)
+                self._parse_block(stop + 1, min(self.closing[stop], end), f"{prefix}{name}::")
+                return self.closing[stop] + 1
+            self._add(keyword, name, prefix, first, signature_start, i, stop, stop, None)
+            return stop + 1
+
+        if keyword == "impl":
+            stop = self._skip_to(i + 1, end, ("{", ";"))
+            self_type = self._impl_self_type(i + 1, stop)
+            if stop < end and tokens[stop].text == "{" and self_type:
+                self._add("impl", self_type, prefix, first, signature_start, i, stop, self.closing[stop], tokens[stop].start)
+                self._parse_block(stop + 1, min(self.closing[stop], end), f"{prefix}{self_type}::")
+            return (self.closing[stop] if stop < end and tokens[stop].text == "{" else stop) + 1
+
+        if keyword in ("struct", "enum", "union") and name:
+            stop = self._skip_to(i + 2, end, ("{", ";"))
+            last = self.closing[stop] if stop < end and tokens[stop].text == "{" else stop
+            self._add(keyword, name, prefix, first, signature_start, i, stop, last, None)
+            return last + 1
+
+        # Anything else (use, const, macro invocations, stray statements) ends
+        # at a semicolon or after a brace group
+        if not prefix:
+            self.other_top_level = True
+        j = i
+        while j < end:
+            text = tokens[j].text
+            if tokens[j].kind == "punct" and text == ";":
+                return j + 1
+            if tokens[j].kind == "punct" and text == "{":
+                j = self.closing[j] + 1
+                if self._text(j) == ";":
+                    j += 1
+                return j
+            if tokens[j].kind == "punct" and text in OPEN_DELIMITERS:
+                j = self.closing[j]
+            j += 1
+        return end
+
+    def _impl_self_type(self, i: int, stop: int) -> Optional[str]:
+        """Find the name of the type an impl block is for."""
+        tokens = [t.text for t in self.tokens[i:stop]]
+
+        # Skip generic parameters directly after "impl"
+        if tokens and tokens[0] == "<":
+            depth = 0
+            for n, text in enumerate(tokens):
+                depth += text == "<"
+                depth -= text == ">"
+                if depth == 0:
+                    tokens = tokens[n + 1:]
+                    break
+
+        depth = 0
+        for n, text in enumerate(tokens):
+            depth += text in ("<", "(", "[")
+            depth -= text in (">", ")", "]")
+            if depth == 0 and text == "for":
+                tokens = tokens[n + 1:]
+                break
+            if depth == 0 and text == "where":
+                tokens = tokens[:n]
+                break
+
+        name = None
+        for text in tokens:
+            if text in ("<", "where"):
+                break
+            if IDENT_PATTERN.fullmatch(text) and text not in ("mut", "dyn", "const"):
+                name = text
+        return name
+
+    def _add(self, kind: str, name: str, prefix: str, first: int, signature_start: int,
+             keyword: int, stop: int, last: int, body_start: Optional[int]):
+        signature_end = self.tokens[stop].start if stop < len(self.tokens) else len(self.source)
+        start = self.tokens[signature_start].start
+        self.items.append(RustItem(
+            kind=kind,
+            name=name,
+            path=f"{prefix}{name}",
+            start=self.tokens[first].start,
+            signature_start=start,
+            end=self._end_offset(last),
+            body_start=body_start,
+            signature=" ".join(self.source[start:signature_end].split()),
+            signature_tokens=[t.text for t in self.tokens[signature_start:stop]],
+        ))
@@ -1,18 +1,27 @@
 """Scoring functions for evaluations."""
 
-from typing import Any, Dict, Optional, List
-from dataclasses import dataclass
+from typing import Any, Callable, Dict, Iterator, Optional, List
+from contextlib import ExitStack, contextmanager
+from dataclasses import dataclass, asdict
 import logging
 import tempfile
 import shutil
-import os
-import subprocess
+import threading
 from pathlib import Path
-from urllib.parse import urlparse
 import git
 
+from .extraction import split_slot_response, extract_code, enclosing_function
+from .patching import PatchAnswer, PatchError, apply_patch_answer, parse_patch_answer
+from .rust_syntax import substitute_item, SubstitutionError
+from .baseline import Baseline, BaselineCache, BaselineDelta, compare_to_baseline, fingerprint_messages, tree_digest
+from .repositories import CheckoutError, GitMirrorCache
+from .test_cases import CaseExpectations, HiddenTest, RustBuildSlot, RustBuildTestCase
+
 logger = logging.getLogger(__name__)
 
+# Output sent back to the model in a repair turn is cut off after this many characters
+MAX_FEEDBACK_CHARS = 8000
+
 
 @dataclass
 class ScorerResult:
@@ -29,9 +38,28 @@ class BaseScorer:
     def __init__(self, name: str):
         self.name = name
     
-    def score(self, prompt: str, response: str, expected: Optional[str] = None) -> ScorerResult:
-        """Score a model response."""
+    def score(self, prompt: str, response: str, expected: Optional[str] = None,
+              metadata: Optional[Dict[str, Any]] = None) -> ScorerResult:
+        """Score a model response.
+        
+        Args:
+            prompt: The prompt the model was given
+            response: The model's response
+            expected: The case's expected value, in a format specific to the scorer
+            metadata: The case's metadata, for scorers that read per-case settings from it
+        """
         raise NotImplementedError
+    
+    def repair_feedback(self, result: ScorerResult) -> Optional[str]:
+        """Explain a failed result to the model for another attempt, or None if there is nothing to tell."""
+        return None
+
+
+def feedback_block(title: str, text: str) -> str:
+    """A fenced block of compiler or program output for a repair turn, cut off at MAX_FEEDBACK_CHARS."""
+    if len(text) > MAX_FEEDBACK_CHARS:
+        text = text[:MAX_FEEDBACK_CHARS] + "\n... (truncated)"
+    return f"{title}:\n```\n{text}\n```"
 
 
 class BasicResponseScorer(BaseScorer):
@@ -41,7 +69,8 @@ class BasicResponseScorer(BaseScorer):
         super().__init__("basic_response")
         self.min_length = min_length
     
-    def score(self, prompt: str, response: str, expected: Optional[str] = None) -> ScorerResult:
+    def score(self, prompt: str, response: str, expected: Optional[str] = None,
+              metadata: Optional[Dict[str, Any]] = None) -> ScorerResult:
         """Check if response is non-empty and meets minimum length."""
         if not response:
             return ScorerResult(
@@ -88,7 +117,8 @@ class LengthScorer(BaseScorer):
         self.min_length = min_length
         self.max_length = max_length
     
-    def score(self, prompt: str, response: str, expected: Optional[str] = None) -> ScorerResult:
+    def score(self, prompt: str, response: str, expected: Optional[str] = None,
+              metadata: Optional[Dict[str, Any]] = None) -> ScorerResult:
         """Score based on response length."""
         length = len(response.strip())
         
@@ -122,7 +152,8 @@ class ContainsScorer(BaseScorer):
         self.required_text = required_text
         self.case_sensitive = case_sensitive
     
-    def score(self, prompt: str, response: str, expected: Optional[str] = None) -> ScorerResult:
+    def score(self, prompt: str, response: str, expected: Optional[str] = None,
+              metadata: Optional[Dict[str, Any]] = None) -> ScorerResult:
         """Check if response contains required text."""
         if self.case_sensitive:
             contains = self.required_text in response
@@ -137,17 +168,35 @@ class ContainsScorer(BaseScorer):
 
 
 @dataclass
-class RustBuildTestCase:
-    """Test case for Rust build evaluation."""
-    repo_url: str
-    tag_or_branch: str
+class SubstitutedRegion:
+    """The part of a file that was replaced with the model's answer."""
+    slot: str
     file_path: str
-    replacement_target: str
-    description: Optional[str] = None
+    line_start: int  # 1-based, inclusive
+    line_end: int
+    byte_start: int  # UTF-8 offsets, end exclusive
+    byte_end: int
+    
+    def contains(self, span: Any) -> bool:
+        """Whether a diagnostic's CodeSpan touches this region."""
+        if Path(span.file_name).as_posix() != Path(self.file_path).as_posix():
+            return False
+        if span.byte_start is not None and span.byte_end is not None:
+            # Zero-width spans (e.g. "expected `;`") count if they sit at the region's edge
+            return span.byte_start <= self.byte_end and self.byte_start <= span.byte_end
+        # Human-format spans only carry lines
+        return span.line_start <= self.line_end and self.line_start <= span.line_end
 
 
 class RustBuildScorer(BaseScorer):
-    """Scorer that evaluates LLM output by substituting it into a Rust project and building."""
+    """Scorer that evaluates LLM output by substituting it into a Rust project and building.
+    
+    Subclasses score other kinds of Rust cases with the same machinery: they
+    set test_case_class and override _evaluate_with_test_case.
+    """
+    
+    # What the expected JSON of a case is read into
+    test_case_class = RustBuildTestCase
     
     def __init__(self, 
                  use_clippy: bool = True,
@@ -155,7 +204,21 @@ class RustBuildScorer(BaseScorer):
                  error_penalty: float = 1.0,
                  warning_penalty: float = 0.1,
                  clippy_penalty: float = 0.05,
-                 timeout: int = 300):
+                 timeout: int = 300,
+                 run_tests: bool = True,
+                 fixtures_root: Optional[Path] = None,
+                 extract_response: bool = True,
+                 clippy_group_penalties: Optional[Dict[str, float]] = None,
+                 memory_limit_mb: Optional[int] = None,
+                 cpu_time_limit: Optional[int] = None,
+                 count_outside_diagnostics: bool = False,
+                 baseline: bool = False,
+                 baseline_cache_dir: Optional[Path] = None,
+                 shared_target_dir: Optional[Path] = None,
+                 target_dir_slots: int = 1,
+                 template_dir: Optional[Path] = None,
+                 template_link: str = "auto",
+                 git_cache_dir: Optional[Path] = None):
         """Initialize the Rust build scorer.
         
         Args:
@@ -164,7 +227,36 @@ class RustBuildScorer(BaseScorer):
             error_penalty: Score penalty per error (default: 1.0 - full penalty)
             warning_penalty: Score penalty per warning (default: 0.1)
             clippy_penalty: Score penalty per clippy lint (default: 0.05)
-            timeout: Timeout in seconds for build operations
+            timeout: Wall-clock seconds each cargo build, clippy or test run may take
+                before it is killed along with its build scripts and test binaries
+            run_tests: Whether to run cargo test and weight the score by the test pass rate
+            fixtures_root: Directory that relative local_path values are resolved against
+                (default: current working directory)
+            extract_response: Whether to extract code from markdown-fenced responses
+                before substitution instead of pasting the raw response
+            clippy_group_penalties: Score penalty per clippy lint by clippy group, e.g.
+                {"correctness": 0.5, "style": 0.01}; other groups use clippy_penalty
+            memory_limit_mb: Address space limit for cargo and each process it spawns
+            cpu_time_limit: CPU seconds limit for cargo and each process it spawns
+            count_outside_diagnostics: Whether errors and warnings outside the substituted
+                regions also count towards the score and pass/fail (by default they are
+                only reported, since the model didn't cause them)
+            baseline: Whether to build the pristine checkout first and also count the
+                diagnostics outside the substituted regions that it doesn't have
+            baseline_cache_dir: Directory to keep baselines in across runs (by default
+                they are only cached for the lifetime of the scorer)
+            shared_target_dir: Directory for cargo target directories kept warm across
+                evaluations of the same repository, so dependencies compile only once
+            target_dir_slots: Target directories per repository, i.e. how many builds
+                of one repository can use a warm directory at the same time
+            template_dir: Directory for prebuilt copies of each repository revision,
+                copied (with reflinks or hardlinks, see template_link) into every
+                evaluation instead of the fresh checkout. With shared_target_dir, the
+                warm directory takes the place of the copy's target/, which seeds the
+                warm directory if it is still empty
+            template_link: How templates are copied: "auto", "reflink", "hardlink" or "copy"
+            git_cache_dir: Directory for the mirrors of repo_url repositories (default:
+                $XDG_CACHE_HOME/openzt-eval/git)
         """
         super().__init__("rust_build")
         self.use_clippy = use_clippy
@@ -173,11 +265,33 @@ class RustBuildScorer(BaseScorer):
         self.warning_penalty = warning_penalty
         self.clippy_penalty = clippy_penalty
         self.timeout = timeout
+        self.run_tests = run_tests
+        self.fixtures_root = fixtures_root
+        self.extract_response = extract_response
+        self.clippy_group_penalties = clippy_group_penalties or {}
+        self.memory_limit_mb = memory_limit_mb
+        self.cpu_time_limit = cpu_time_limit
+        self.count_outside_diagnostics = count_outside_diagnostics
+        self.baseline = baseline
+        self.baseline_cache = BaselineCache(baseline_cache_dir)
+        # Responses may be scored from several threads; each baseline is built once
+        self._baseline_locks: Dict[str, threading.Lock] = {}
+        self._baseline_locks_guard = threading.Lock()
+        
+        from cargo_orchestrator.cache import TargetDirPool, TemplateCache
+        
+        self.target_dirs = TargetDirPool(shared_target_dir, target_dir_slots) if shared_target_dir else None
+        self.template_cache = TemplateCache(template_dir, template_link) if template_dir else None
+        # Target directory held by the evaluation of each checkout
+        self._active_target_dirs: Dict[Path, Path] = {}
+        self.git_mirrors = GitMirrorCache(git_cache_dir)
     
-    def score(self, prompt: str, response: str, expected: Optional[str] = None) -> ScorerResult:
-        """Score the response by building a Rust project with the substitution.
+    def score(self, prompt: str, response: str, expected: Optional[str] = None,
+              metadata: Optional[Dict[str, Any]] = None) -> ScorerResult:
+        """Score the response against the case given as JSON in expected.
         
-        The expected parameter should be a JSON string containing RustBuildTestCase data:
+        The expected parameter should be a JSON string containing the fields of
+        test_case_class; for RustBuildScorer, RustBuildTestCase data:
         {
             "repo_url": "https://github.com/user/repo",
             "tag_or_branch": "main",
@@ -185,6 +299,25 @@ class RustBuildScorer(BaseScorer):
             "replacement_target": "// TODO: implement this function",
             "description": "Optional description"
         }
+        
+        Instead of repo_url and tag_or_branch, a local crate directory can be
+        given as "local_path" (relative paths are resolved against fixtures_root).
+        
+        Instead of replacement_target, "item_path" can name a function such as
+        "Stack::pop". The file is parsed and either the whole item or only its
+        body is replaced ("replace": "auto", "item" or "body"), after checking
+        that a returned definition keeps the original public signature.
+        
+        Instead of file_path and replacement_target, several substitution points
+        can be given as "slots", each with a "name", "file_path" and
+        "replacement_target". The response must then answer every slot, either as
+        a JSON object keyed by slot name or as fenced code blocks labelled with
+        the slot name (```rust <name>).
+        
+        The case's metadata can set expectations (see CaseExpectations):
+        "expected_errors" and "expected_warnings" budgets, a "time_limit" in
+        seconds for running the tests, and overrides of "allow_warnings" and
+        the "error_penalty", "warning_penalty" and "clippy_penalty".
         """
         if not expected:
             return ScorerResult(
@@ -195,36 +328,96 @@ class RustBuildScorer(BaseScorer):
         
         try:
             import json
-            test_case_data = json.loads(expected)
-            test_case = RustBuildTestCase(**test_case_data)
-        except (json.JSONDecodeError, TypeError) as e:
+            test_case = self.test_case_class(**json.loads(expected))
+            expectations = CaseExpectations.from_metadata(metadata)
+        except (json.JSONDecodeError, TypeError, ValueError) as e:
             return ScorerResult(
                 score=0.0,
                 passed=False,
                 reason=f"Invalid test case configuration: {e}"
             )
         
-        return self._evaluate_with_test_case(test_case, response)
+        return self._evaluate_with_test_case(test_case, response, expectations)
     
-    def _evaluate_with_test_case(self, test_case: RustBuildTestCase, response: str) -> ScorerResult:
+    def repair_feedback(self, result: ScorerResult) -> Optional[str]:
+        """Send back the reason, the failing tests and the compiler's rendered diagnostics."""
+        metadata = result.metadata or {}
+        parts = [f"The code did not pass: {result.reason}."]
+        if metadata.get("failed_tests"):
+            parts.append("Failing tests: " + ", ".join(metadata["failed_tests"]))
+        if metadata.get("failed_doc_tests"):
+            parts.append("Failing doc tests: " + ", ".join(metadata["failed_doc_tests"]))
+        if metadata.get("diagnostics"):
+            parts.append(feedback_block("Compiler output", "\n\n".join(metadata["diagnostics"])))
+        return "\n\n".join(parts)
+    
+    def _evaluate_with_test_case(self, test_case: RustBuildTestCase, response: str,
+                                 expectations: Optional[CaseExpectations] = None) -> ScorerResult:
         """Evaluate the response with a specific test case."""
-        with tempfile.TemporaryDirectory() as temp_dir:
+        expectations = expectations or CaseExpectations()
+        with tempfile.TemporaryDirectory() as temp_dir, ExitStack() as stack:
             temp_path = Path(temp_dir)
             
             try:
-                # Clone the repository
-                repo_path = self._clone_repository(test_case.repo_url, test_case.tag_or_branch, temp_path)
-                
-                # Perform the substitution
-                target_file = repo_path / test_case.file_path
-                if not target_file.exists():
-                    return ScorerResult(
-                        score=0.0,
-                        passed=False,
-                        reason=f"Target file not found: {test_case.file_path}"
-                    )
+                # Check out the pinned revision or copy the local fixture
+                repo_path, revision = stack.enter_context(self._warm_checkout(test_case, temp_path))
                 
-                self._perform_substitution(target_file, test_case.replacement_target, response)
+                extraction = None
+                item_substitutions = {}
+                patch_report = None
+                if test_case.response_format == "patch":
+                    try:
+                        answer, patch_report, baseline, _ = self._apply_answer(test_case, response,
+                                                                               repo_path, revision)
+                    except PatchError as e:
+                        return self._patch_failure(e, repo_path, revision)
+                    # The whole crate is the model's to fix, so every diagnostic counts
+                    regions = None
+                else:
+                    # Split the response across the slots and check each one before building
+                    slots = test_case.get_slots()
+                    if test_case.slots:
+                        answers = split_slot_response(response, [slot.name for slot in slots])
+                    else:
+                        answers = {slots[0].name: response}
+                    
+                    if self.extract_response:
+                        extraction = self._extract_answers(repo_path, slots, answers)
+                    
+                    slot_errors = self._validate_slots(repo_path, slots, answers)
+                    if slot_errors:
+                        metadata = {
+                            "failure": "substitution",
+                            "slot_errors": slot_errors,
+                            "slots_answered": sorted(answers)
+                        }
+                        metadata.update(self._provenance(repo_path, revision))
+                        if extraction:
+                            metadata["extraction"] = extraction
+                        return ScorerResult(
+                            score=0.0,
+                            passed=False,
+                            reason="; ".join(slot_errors.values()),
+                            metadata=metadata
+                        )
+                    
+                    # Build the pristine checkout first, unless it is cached
+                    baseline = self._get_baseline(test_case, repo_path, revision) if self.baseline else None
+                    
+                    # Perform the substitutions, keeping track of the changed regions
+                    changes: Dict[str, List[List[Any]]] = {}
+                    for slot in slots:
+                        target_file = repo_path / slot.file_path
+                        before = target_file.read_text(encoding='utf-8')
+                        if slot.item_path:
+                            item_substitutions[slot.name] = self._perform_item_substitution(
+                                target_file, slot.item_path, answers[slot.name], slot.replace
+                            )
+                        else:
+                            self._perform_substitution(target_file, slot.replacement_target, answers[slot.name])
+                        self._track_change(changes.setdefault(slot.file_path, []), slot.name,
+                                           before, target_file.read_text(encoding='utf-8'))
+                    regions = self._substituted_regions(repo_path, changes)
                 
                 # Run cargo build and optionally clippy
                 build_result = self._run_cargo_build(repo_path)
@@ -232,9 +425,43 @@ class RustBuildScorer(BaseScorer):
                 if self.use_clippy:
                     clippy_result = self._run_cargo_clippy(repo_path)
                 
+                # Only run the tests if there is something to test; hidden tests are
+                # added only now, so that they never show up in build or clippy diagnostics
+                test_result = None
+                if (self.run_tests or test_case.hidden_tests) and build_result.success:
+                    if test_case.hidden_tests:
+                        self._write_hidden_tests(repo_path, test_case.hidden_tests)
+                    test_result = self._run_cargo_test(repo_path, all_targets=bool(test_case.hidden_tests),
+                                                       time_limit=expectations.time_limit)
+                
+                baseline_deltas = None
+                if baseline:
+                    baseline_deltas = {"build": compare_to_baseline(baseline.build, build_result.messages, repo_path)}
+                    if clippy_result and baseline.clippy is not None:
+                        baseline_deltas["clippy"] = compare_to_baseline(baseline.clippy, clippy_result.messages, repo_path)
+                
                 # Calculate score
-                return self._calculate_score(build_result, clippy_result, test_case)
+                result = self._calculate_score(build_result, clippy_result, test_case, test_result, regions,
+                                               baseline_deltas, expectations)
+                if baseline:
+                    result.metadata["baseline"] = {
+                        "key": baseline.key,
+                        "build_status": baseline.build_status,
+                        "cached": baseline.cached,
+                        **{name: delta.to_metadata() for name, delta in baseline_deltas.items()}
+                    }
+                result.metadata.update(self._provenance(repo_path, revision))
+                if extraction:
+                    result.metadata["extraction"] = extraction
+                if item_substitutions:
+                    result.metadata["item_substitutions"] = item_substitutions
+                if patch_report:
+                    result.metadata["patch"] = {"kind": answer.kind, "files": patch_report}
+                return result
                 
+            except CheckoutError:
+                # The test case is broken, not the response; don't score it
+                raise
             except Exception as e:
                 logger.error(f"Error during Rust build evaluation: {e}")
                 return ScorerResult(
@@ -243,24 +470,276 @@ class RustBuildScorer(BaseScorer):
                     reason=f"Evaluation failed: {str(e)}"
                 )
     
-    def _clone_repository(self, repo_url: str, tag_or_branch: str, temp_path: Path) -> Path:
-        """Clone the repository to a temporary directory."""
-        repo_path = temp_path / "repo"
+    def _apply_answer(self, test_case: Any, response: str, repo_path: Path, revision: Dict[str, Any],
+                      baseline_required: bool = False,
+                      inspect_pristine: Optional[Callable[[Baseline], Any]] = None) -> tuple:
+        """Check a patch answer and apply it, building the pristine checkout first.
         
-        logger.info(f"Cloning {repo_url} (branch: {tag_or_branch}) to {repo_path}")
+        The pristine checkout is built if the scorer compares against a baseline
+        or baseline_required is set; inspect_pristine is then called with the
+        baseline before anything is written.
         
-        # Clone the repository
-        repo = git.Repo.clone_from(repo_url, repo_path)
+        Returns:
+            The answer, what happened to each file, the baseline (or None) and
+            what inspect_pristine returned
         
-        # Checkout the specific tag or branch
+        Raises:
+            PatchError: If the answer can't be read, changes files it may not, or doesn't apply
+        """
+        answer = self._patch_answer(response, test_case)
+        
+        # Build the pristine checkout first, unless it is cached
+        baseline = None
+        pristine = None
+        if self.baseline or baseline_required:
+            baseline = self._get_baseline(test_case, repo_path, revision)
+            if baseline is None and baseline_required:
+                raise RuntimeError("The build of the unmodified crate timed out")
+            if baseline is not None and inspect_pristine:
+                pristine = inspect_pristine(baseline)
+        
+        patch_report = apply_patch_answer(repo_path, answer, test_case.fuzz)
+        return answer, patch_report, baseline, pristine
+    
+    def _patch_answer(self, response: str, test_case: RustBuildTestCase) -> PatchAnswer:
+        """Read the diff or labelled files, keeping them off what the crate is judged by."""
+        answer = parse_patch_answer(response)
+        hidden = {Path(test.file_path).as_posix() for test in test_case.hidden_tests or []}
+        
+        # Both sides of a diff, so a protected file can't be renamed away either
+        changed = [path for patch in answer.patches for path in (patch.old_path, patch.new_path) if path]
+        changed += list(answer.files)
+        for path in changed:
+            parts = Path(path).parts
+            if ".cargo" in parts:
+                # Cargo configuration can redirect sources, wherever cargo looks for it
+                raise PatchError(f"{path}: cargo configuration may not be changed")
+            if parts[0] == "tests" or Path(path).as_posix() in hidden:
+                raise PatchError(f"{path}: tests may not be changed")
+            if parts[-1] in ("Cargo.toml", "build.rs") and not test_case.allow_manifest_changes:
+                raise PatchError(f"{path}: the manifest and build scripts may not be changed")
+        return answer
+    
+    def _patch_failure(self, error: PatchError, repo_path: Path, revision: Dict[str, Any]) -> ScorerResult:
+        """Score an answer that doesn't apply; it is reported apart from build failures."""
+        metadata = {"failure": "patch", "patch_error": str(error)}
+        metadata.update(self._provenance(repo_path, revision))
+        return ScorerResult(
+            score=0.0,
+            passed=False,
+            reason=f"Patch failed to apply: {error}",
+            metadata=metadata
+        )
+    
+    def _revision_key(self, test_case: Any, revision: Dict[str, Any]) -> str:
+        """Identify the checked out revision, for caches kept per repository and revision."""
+        if test_case.local_path:
+            return f"{test_case.local_path}@{revision['tree_digest']}"
+        return f"{test_case.repo_url}@{revision['commit']}"
+    
+    def _local_revision(self, local_path: str, repo_path: Path) -> Dict[str, Any]:
+        """Describe a copied fixture by its content and, if it is under git, the commit it is at."""
+        revision = {"local_path": local_path, "tree_digest": tree_digest(repo_path)}
+        # Fixtures usually live in a repository, e.g. the evaluation's own
+        source = self._fixture_path(local_path)
         try:
-            repo.git.checkout(tag_or_branch)
-        except git.exc.GitCommandError as e:
-            logger.warning(f"Failed to checkout {tag_or_branch}: {e}")
-            # Continue with default branch
+            repo = git.Repo(source, search_parent_directories=True)
+            revision["commit"] = repo.head.commit.hexsha
+            revision["dirty"] = bool(repo.git.status("--porcelain", "--", str(source)))
+        except (git.exc.InvalidGitRepositoryError, git.exc.NoSuchPathError, git.exc.GitCommandError, ValueError):
+            pass
+        return revision
+    
+    def _provenance(self, repo_path: Path, revision: Dict[str, Any]) -> Dict[str, Any]:
+        """Metadata identifying the revision under test and the toolchain that built it."""
+        return {
+            "revision": revision,
+            "toolchain": self._create_builder(repo_path).toolchain.to_dict()
+        }
+    
+    @contextmanager
+    def _checkout(self, test_case: Any, temp_path: Path) -> Iterator[tuple]:
+        """Check out the case's revision or copy the local fixture, using a prebuilt template if configured.
+        
+        Yields:
+            The checkout's path and a description of the revision: the repository
+            and commit SHA, or for local fixtures a digest of the copied tree
+        
+        Raises:
+            CheckoutError: If the repository or the revision isn't available
+        """
+        with ExitStack() as worktree:
+            if test_case.local_path:
+                repo_path = self._copy_local_fixture(test_case.local_path, temp_path)
+                revision = self._local_revision(test_case.local_path, repo_path)
+            else:
+                commit = self.git_mirrors.resolve(test_case.repo_url, test_case.tag_or_branch)
+                repo_path = worktree.enter_context(
+                    self.git_mirrors.worktree(test_case.repo_url, commit, temp_path / "repo")
+                )
+                revision = {"repo_url": test_case.repo_url, "tag_or_branch": test_case.tag_or_branch,
+                            "commit": commit}
+            if not self.template_cache:
+                yield repo_path, revision
+                return
+            
+            key = self._revision_key(test_case, revision)
+            warm_path = temp_path / "warm"
+            if self.template_cache.checkout(key, repo_path, warm_path, self._prepare_template):
+                logger.info(f"Using prebuilt template for {key}")
+        if test_case.local_path:
+            shutil.rmtree(repo_path)
+        yield warm_path, revision
+    
+    def _prepare_template(self, template_path: Path):
+        """Compile the dependencies of a template, for every cargo command the scorer runs."""
+        logger.info(f"Prebuilding template {template_path}")
+        builder = self._create_builder(template_path)
+        builder.build()
+        if self.use_clippy:
+            builder.clippy()
+        if self.run_tests:
+            builder.test(extra_args=["--no-run"])
+    
+    @contextmanager
+    def _warm_checkout(self, test_case: Any, temp_path: Path) -> Iterator[tuple]:
+        """Check out the case's crate, holding a warm target directory for its builds if configured."""
+        with self._checkout(test_case, temp_path) as (repo_path, revision), \
+                self._shared_target_dir(test_case, repo_path):
+            yield repo_path, revision
+    
+    @contextmanager
+    def _shared_target_dir(self, test_case: Any, repo_path: Path) -> Iterator[None]:
+        """Hold a warm target directory for the builds of a checkout, if configured."""
+        if not self.target_dirs:
+            yield
+            return
+        with self.target_dirs.acquire(self._target_dir_key(test_case)) as target_dir:
+            # A prebuilt template's target/ would be shadowed by the warm directory
+            prebuilt = repo_path / "target"
+            if prebuilt.is_dir() and not any(target_dir.iterdir()):
+                for entry in prebuilt.iterdir():
+                    shutil.move(str(entry), str(target_dir / entry.name))
+            self._active_target_dirs[repo_path] = target_dir
+            try:
+                yield
+            finally:
+                del self._active_target_dirs[repo_path]
+    
+    def _target_dir_key(self, test_case: Any) -> str:
+        """The repository whose warm target directories a case's builds use."""
+        return test_case.local_path or test_case.repo_url
+    
+    def _get_baseline(self, test_case: RustBuildTestCase, repo_path: Path,
+                      revision: Dict[str, Any]) -> Optional[Baseline]:
+        """Build the pristine checkout, or look up its diagnostics if this revision was built before."""
+        key = self._revision_key(test_case, revision)
+        if self.use_clippy:
+            key += "+clippy"
+        
+        with self._baseline_locks_guard:
+            lock = self._baseline_locks.setdefault(key, threading.Lock())
+        with lock:
+            baseline = self.baseline_cache.get(key)
+            if baseline:
+                return baseline
+            return self._build_baseline(key, repo_path)
+    
+    def _build_baseline(self, key: str, repo_path: Path) -> Optional[Baseline]:
+        """Build the pristine checkout and fingerprint its diagnostics."""
+        logger.info(f"Building baseline for {key}")
+        build_result = self._run_cargo_build(repo_path)
+        if build_result.timed_out:
+            logger.warning(f"Baseline build of {key} timed out; scoring without a baseline")
+            return None
+        clippy_messages = None
+        if self.use_clippy:
+            clippy_result = self._run_cargo_clippy(repo_path)
+            if clippy_result.timed_out:
+                logger.warning(f"Baseline clippy run of {key} timed out; scoring without a baseline")
+                return None
+            clippy_messages = [d for _, d in fingerprint_messages(clippy_result.messages, repo_path)]
+        
+        baseline = Baseline(
+            key=key,
+            build_status=build_result.status.value,
+            build=[d for _, d in fingerprint_messages(build_result.messages, repo_path)],
+            clippy=clippy_messages
+        )
+        self.baseline_cache.put(baseline)
+        return baseline
+    
+    def _fixture_path(self, local_path: str) -> Path:
+        """Resolve a local_path against fixtures_root."""
+        source_path = Path(local_path)
+        if not source_path.is_absolute():
+            source_path = (self.fixtures_root or Path.cwd()) / source_path
+        return source_path
+    
+    def _copy_local_fixture(self, local_path: str, temp_path: Path) -> Path:
+        """Copy a local crate directory to a temporary directory."""
+        source_path = self._fixture_path(local_path)
+        
+        if not (source_path / "Cargo.toml").is_file():
+            raise FileNotFoundError(f"No Cargo.toml found in local fixture: {source_path}")
+        
+        repo_path = temp_path / "repo"
+        
+        logger.info(f"Copying local fixture {source_path} to {repo_path}")
+        
+        # Build output and VCS metadata are not part of the fixture
+        shutil.copytree(source_path, repo_path, ignore=shutil.ignore_patterns("target", ".git"))
         
         return repo_path
     
+    def _extract_answers(self, repo_path: Path, slots: List[RustBuildSlot], answers: Dict[str, str]) -> Dict[str, Any]:
+        """Replace each answer with the code extracted from it, returning what was extracted."""
+        extraction = {}
+        
+        for slot in slots:
+            if slot.name not in answers:
+                continue
+            
+            # A repeated signature is only stripped when the slot sits inside that function.
+            # Item slots keep it, so the signature can be checked against the original.
+            function_name = None
+            target_file = repo_path / slot.file_path
+            if target_file.exists() and slot.replacement_target:
+                content = target_file.read_text(encoding='utf-8')
+                offset = content.find(slot.replacement_target)
+                if offset != -1:
+                    function_name = enclosing_function(content, offset)
+            
+            extracted = extract_code(answers[slot.name], function_name)
+            answers[slot.name] = extracted.code
+            extraction[slot.name] = extracted.to_metadata()
+        
+        return extraction
+    
+    def _validate_slots(self, repo_path: Path, slots: List[RustBuildSlot], answers: Dict[str, str]) -> Dict[str, str]:
+        """Check every slot independently, returning an error message per invalid slot."""
+        errors = {}
+        
+        for slot in slots:
+            target_file = repo_path / slot.file_path
+            if not target_file.exists():
+                errors[slot.name] = f"Target file not found: {slot.file_path}"
+            elif slot.replacement_target and slot.replacement_target not in target_file.read_text(encoding='utf-8'):
+                errors[slot.name] = f"Replacement target not found in {slot.file_path}: {slot.replacement_target}"
+            elif slot.name not in answers:
+                errors[slot.name] = f"No answer for slot '{slot.name}'"
+            elif not answers[slot.name].strip():
+                errors[slot.name] = f"Empty answer for slot '{slot.name}'"
+            elif slot.item_path:
+                # Dry run against the pristine file to check the item and signature
+                try:
+                    substitute_item(target_file.read_text(encoding='utf-8'), slot.item_path,
+                                    answers[slot.name], slot.replace)
+                except SubstitutionError as e:
+                    errors[slot.name] = f"{slot.file_path}: {e}"
+        
+        return errors
+    
     def _perform_substitution(self, target_file: Path, replacement_target: str, replacement_text: str):
         """Replace the target string with the LLM response in the target file."""
         logger.info(f"Performing substitution in {target_file}")
@@ -276,12 +755,87 @@ class RustBuildScorer(BaseScorer):
         
         logger.info(f"Substituted {len(replacement_target)} chars with {len(replacement_text)} chars")
     
+    def _track_change(self, changes: List[List[Any]], slot_name: str, before: str, after: str):
+        """Record the changed range of a file as [slot, start, end] character offsets.
+        
+        Ranges recorded for earlier slots in the same file are shifted to stay
+        valid in the new content.
+        """
+        prefix = 0
+        limit = min(len(before), len(after))
+        while prefix < limit and before[prefix] == after[prefix]:
+            prefix += 1
+        suffix = 0
+        while suffix < limit - prefix and before[-1 - suffix] == after[-1 - suffix]:
+            suffix += 1
+        
+        delta = len(after) - len(before)
+        for change in changes:
+            if change[1] >= len(before) - suffix:
+                change[1] += delta
+                change[2] += delta
+        changes.append([slot_name, prefix, len(after) - suffix])
+    
+    def _substituted_regions(self, repo_path: Path, changes: Dict[str, List[List[Any]]]) -> List[SubstitutedRegion]:
+        """Convert the tracked character ranges to line and byte ranges."""
+        regions = []
+        for file_path, file_changes in changes.items():
+            content = (repo_path / file_path).read_text(encoding='utf-8')
+            for slot_name, start, end in file_changes:
+                regions.append(SubstitutedRegion(
+                    slot=slot_name,
+                    file_path=file_path,
+                    line_start=content.count('\n', 0, start) + 1,
+                    line_end=content.count('\n', 0, max(start, end - 1)) + 1,
+                    byte_start=len(content[:start].encode('utf-8')),
+                    byte_end=len(content[:end].encode('utf-8'))
+                ))
+        return regions
+    
+    def _locate_message(self, message: Any, regions: Optional[List[SubstitutedRegion]]) -> str:
+        """Classify a diagnostic as "inside" or "outside" the substituted regions, or "unlocated".
+        
+        Secondary spans count too: a body without a tail expression is reported
+        at the signature's return type, with the body only as a secondary span.
+        """
+        if not message.spans:
+            return "unlocated"
+        if regions is None:
+            return "inside"
+        if any(region.contains(span) for span in message.spans for region in regions):
+            return "inside"
+        return "outside"
+    
+    def _perform_item_substitution(self, target_file: Path, item_path: str, replacement_text: str,
+                                   replace: str) -> str:
+        """Replace a function item or its body with the LLM response, returning the mode used."""
+        logger.info(f"Performing {replace} substitution of {item_path} in {target_file}")
+        
+        content = target_file.read_text(encoding='utf-8')
+        new_content, mode = substitute_item(content, item_path, replacement_text, replace)
+        target_file.write_text(new_content, encoding='utf-8')
+        
+        logger.info(f"Substituted {item_path} ({mode}) with {len(replacement_text)} chars")
+        return mode
+    
+    def _create_builder(self, repo_path: Path) -> Any:
+        """Create a CargoBuilder enforcing the scorer's timeout and resource limits."""
+        from cargo_orchestrator import CargoBuilder, ResourceLimits
+        
+        limits = None
+        if self.memory_limit_mb or self.cpu_time_limit:
+            limits = ResourceLimits(
+                memory_bytes=self.memory_limit_mb * 1024 * 1024 if self.memory_limit_mb else None,
+                cpu_seconds=self.cpu_time_limit
+            )
+        
+        return CargoBuilder(root_dir=repo_path, timeout=self.timeout, limits=limits,
+                            target_dir=self._active_target_dirs.get(repo_path))
+    
     def _run_cargo_build(self, repo_path: Path) -> Any:
         """Run cargo build and return the result."""
         try:
-            from cargo_orchestrator import CargoBuilder
-            
-            builder = CargoBuilder(root_dir=repo_path)
+            builder = self._create_builder(repo_path)
             result = builder.build()
             
             logger.info(f"Cargo build completed: success={result.success}, "
@@ -294,9 +848,7 @@ class RustBuildScorer(BaseScorer):
     def _run_cargo_clippy(self, repo_path: Path) -> Any:
         """Run cargo clippy and return the result."""
         try:
-            from cargo_orchestrator import CargoBuilder
-            
-            builder = CargoBuilder(root_dir=repo_path)
+            builder = self._create_builder(repo_path)
             result = builder.clippy()
             
             logger.info(f"Cargo clippy completed: success={result.success}, "
@@ -306,54 +858,241 @@ class RustBuildScorer(BaseScorer):
         except ImportError:
             raise RuntimeError("cargo-orchestrator library is required for RustBuildScorer")
     
-    def _calculate_score(self, build_result: Any, clippy_result: Any, test_case: RustBuildTestCase) -> ScorerResult:
-        """Calculate the final score based on build and clippy results."""
-        from cargo_orchestrator.parser import MessageLevel
+    def _write_hidden_tests(self, repo_path: Path, hidden_tests: List[HiddenTest]):
+        """Add the hidden tests to the checkout."""
+        for test in hidden_tests:
+            content = test.content
+            if test.source:
+                content = self._fixture_path(test.source).read_text(encoding='utf-8')
+            target_file = repo_path / test.file_path
+            if test.integration:
+                if target_file.exists():
+                    raise FileExistsError(f"Hidden test {test.file_path} already exists in the crate")
+                target_file.parent.mkdir(parents=True, exist_ok=True)
+                target_file.write_text(content, encoding='utf-8')
+            else:
+                if not target_file.is_file():
+                    raise FileNotFoundError(f"Hidden test module target not found: {test.file_path}")
+                with open(target_file, "a", encoding='utf-8') as f:
+                    f.write(f"\n#[cfg(test)]\nmod {test.module} {{\n{content.rstrip()}\n}}\n")
+    
+    def _run_cargo_test(self, repo_path: Path, all_targets: bool = False,
+                        time_limit: Optional[float] = None) -> Any:
+        """Run cargo test and return the result.
+        
+        With all_targets, every test target runs even if an earlier one fails
+        (cargo test stops at the first failing target by default). A time_limit
+        applies to running the tests only: they are compiled first, within the
+        scorer's timeout.
+        """
+        try:
+            builder = self._create_builder(repo_path)
+            extra_args = ["--no-fail-fast"] if all_targets else []
+            if time_limit is not None:
+                compiled = builder.test(extra_args=extra_args + ["--no-run"])
+                if not compiled.success:
+                    return compiled
+                builder.timeout = time_limit
+            result = builder.test(extra_args=extra_args)
+            
+            passed = sum(1 for t in result.tests if t.passed)
+            logger.info(f"Cargo test completed: success={result.success}, "
+                       f"passed={passed}/{len(result.tests)}, return_code={result.return_code}")
+            
+            return result
+        except ImportError:
+            raise RuntimeError("cargo-orchestrator library is required for RustBuildScorer")
+    
+    def _calculate_score(self, build_result: Any, clippy_result: Any, test_case: Any,
+                         test_result: Any = None,
+                         regions: Optional[List[SubstitutedRegion]] = None,
+                         baseline_deltas: Optional[Dict[str, BaselineDelta]] = None,
+                         expectations: Optional[CaseExpectations] = None) -> ScorerResult:
+        """Calculate the final score based on build and clippy results.
+        
+        Only diagnostics inside the substituted regions are counted, unless
+        count_outside_diagnostics is set or no regions are given. Diagnostics
+        elsewhere in the crate are reported under outside_diagnostics. With
+        baseline deltas, those outside diagnostics are counted if the pristine
+        checkout doesn't have them. The case's expectations override the
+        scorer's penalties and allow_warnings, and add error and warning budgets.
+        The test case is a RustBuildTestCase or a RustCrateTestCase.
+        """
+        from cargo_orchestrator.parser import MessageLevel, TestOutcome
+        
+        expectations = expectations or CaseExpectations()
+        
+        def setting(name: str) -> Any:
+            value = getattr(expectations, name)
+            return getattr(self, name) if value is None else value
+        
+        if self.count_outside_diagnostics and not baseline_deltas:
+            regions = None
+        baseline_deltas = baseline_deltas or {}
+        outside = {"errors": 0, "warnings": 0, "clippy_lints": 0, "unlocated": 0}
+        
+        def counted(messages: List[Any], delta: Optional[BaselineDelta]) -> tuple:
+            """Split off the diagnostics that don't count; returns (counted, outside errors)."""
+            result = []
+            errors_outside = 0
+            new = {id(msg) for msg in delta.new} if delta else set()
+            for msg in messages:
+                if msg.level not in (MessageLevel.ERROR, MessageLevel.WARNING):
+                    continue
+                location = self._locate_message(msg, regions)
+                if location == "outside" and id(msg) in new:
+                    location = "inside"
+                if location == "outside":
+                    if msg.is_clippy_lint:
+                        outside["clippy_lints"] += 1
+                    elif msg.level == MessageLevel.ERROR:
+                        outside["errors"] += 1
+                        errors_outside += 1
+                    else:
+                        outside["warnings"] += 1
+                    continue
+                if location == "unlocated" and regions is not None:
+                    # Summaries such as "aborting due to 2 previous errors" repeat located diagnostics
+                    outside["unlocated"] += 1
+                    continue
+                result.append(msg)
+            return result, errors_outside
+        
+        build_messages, build_errors_outside = counted(build_result.messages, baseline_deltas.get("build"))
+        clippy_messages, clippy_errors_outside = (
+            counted(clippy_result.messages, baseline_deltas.get("clippy")) if clippy_result else ([], 0)
+        )
         
         # Count different types of messages
-        build_errors = sum(1 for msg in build_result.messages if msg.level == MessageLevel.ERROR)
-        build_warnings = sum(1 for msg in build_result.messages if msg.level == MessageLevel.WARNING)
+        build_errors = sum(1 for msg in build_messages if msg.level == MessageLevel.ERROR)
+        build_warnings = sum(1 for msg in build_messages if msg.level == MessageLevel.WARNING)
         
         clippy_errors = 0
         clippy_warnings = 0
         clippy_lints = 0
+        clippy_lints_by_group: Dict[str, int] = {}
+        clippy_lint_penalty = 0.0
         
         if clippy_result:
-            clippy_errors = sum(1 for msg in clippy_result.messages if msg.level == MessageLevel.ERROR)
-            clippy_warnings = sum(1 for msg in clippy_result.messages if msg.level == MessageLevel.WARNING)
-            # Count clippy-specific lints
-            clippy_lints = sum(1 for msg in clippy_result.messages 
-                             if msg.code and "clippy::" in msg.code)
+            clippy_errors = sum(1 for msg in clippy_messages if msg.level == MessageLevel.ERROR)
+            clippy_warnings = sum(1 for msg in clippy_messages if msg.level == MessageLevel.WARNING)
+            # Count clippy-specific lints, penalized by their clippy group
+            for msg in clippy_messages:
+                if not msg.is_clippy_lint:
+                    continue
+                group = msg.lint_group or "unknown"
+                clippy_lints += 1
+                clippy_lints_by_group[group] = clippy_lints_by_group.get(group, 0) + 1
+                clippy_lint_penalty += self.clippy_group_penalties.get(group, setting("clippy_penalty"))
         
         total_errors = build_errors + clippy_errors
         total_warnings = build_warnings + clippy_warnings
         
+        # The compiler's own rendering of what counted, e.g. for sending back to the model
+        diagnostics: List[str] = []
+        for msg in build_messages + clippy_messages:
+            text = (msg.rendered or f"{msg.level.value}: {msg.message}").rstrip()
+            if text not in diagnostics:
+                diagnostics.append(text)
+        
         # Calculate score (start from 1.0 and subtract penalties)
         score = 1.0
-        score -= total_errors * self.error_penalty
-        score -= total_warnings * self.warning_penalty
-        score -= clippy_lints * self.clippy_penalty
+        score -= total_errors * setting("error_penalty")
+        score -= total_warnings * setting("warning_penalty")
+        score -= clippy_lint_penalty
         
         # Ensure score is between 0 and 1
         score = max(0.0, min(1.0, score))
         
+        # A build that only fails outside the substituted regions is not held against
+        # the model, but it leaves the tests unrun; if they were required, nothing
+        # shows that the answer works
+        build_failed_outside = (
+            not build_result.success and not build_result.timed_out
+            and build_errors == 0 and build_errors_outside > 0
+        )
+        hidden_tests = test_case.hidden_tests or []
+        tests_not_run = build_failed_outside and (self.run_tests or bool(hidden_tests))
+        
+        # Weight by the fraction of #[test] functions that pass (ignored tests don't count);
+        # doc tests are reported separately and only decide whether the case passes
+        tests_passed = 0
+        tests_failed = 0
+        test_pass_rate = 1.0
+        hidden = []
+        harness_tests = []
+        doc_tests = []
+        if tests_not_run:
+            test_pass_rate = 0.0
+            score *= test_pass_rate
+        elif test_result:
+            hidden = [t for t in test_result.tests if any(h.covers(t) for h in hidden_tests)]
+            harness_tests = [t for t in test_result.tests if not t.doc_test]
+            doc_tests = [t for t in test_result.tests if t.doc_test]
+            tests_passed = sum(1 for t in harness_tests if t.outcome == TestOutcome.PASSED)
+            tests_failed = sum(1 for t in harness_tests if t.outcome == TestOutcome.FAILED)
+            tests_run = tests_passed + tests_failed
+            if test_result.timed_out:
+                # The tests that never finished are unknown, so nothing counts as passed
+                test_pass_rate = 0.0
+            elif tests_run:
+                test_pass_rate = tests_passed / tests_run
+            elif not test_result.success:
+                # The test harness itself failed to build or run
+                test_pass_rate = 0.0
+            score *= test_pass_rate
+        
         # Determine if it passes
-        build_passed = build_result.success
-        clippy_passed = clippy_result.success if clippy_result else True
+        build_passed = build_result.success or build_failed_outside
+        if clippy_result:
+            clippy_passed = clippy_result.success or (
+                not clippy_result.timed_out and clippy_errors == 0 and clippy_errors_outside > 0
+            )
+        else:
+            clippy_passed = True
+        tests_ok = test_result.success if test_result else not tests_not_run
         
-        # Pass if build succeeds and (warnings allowed or no warnings)
-        passed = build_passed and clippy_passed and (self.allow_warnings or total_warnings == 0)
+        # Pass if build succeeds, tests pass, (warnings allowed or no warnings) and within the case's budgets
+        errors_over_budget = (expectations.expected_errors is not None
+                              and total_errors > expectations.expected_errors)
+        warnings_over_budget = (expectations.expected_warnings is not None
+                                and total_warnings > expectations.expected_warnings)
+        passed = (build_passed and clippy_passed and tests_ok
+                  and not errors_over_budget and not warnings_over_budget
+                  and (setting("allow_warnings") or total_warnings == 0))
         
         # Generate reason
         reason_parts = []
-        if not build_passed:
+        if build_result.timed_out:
+            reason_parts.append(f"Build timed out after {self.timeout} seconds")
+        elif not build_passed:
             reason_parts.append(f"Build failed with {build_errors} errors")
-        if not clippy_passed:
+        if clippy_result and clippy_result.timed_out:
+            reason_parts.append(f"Clippy timed out after {self.timeout} seconds")
+        elif not clippy_passed:
             reason_parts.append(f"Clippy failed with {clippy_errors} errors")
-        if total_warnings > 0:
+        if build_failed_outside:
+            reason_parts.append(f"Tests not run: build failed with {build_errors_outside} errors "
+                                "outside the substituted region")
+        if errors_over_budget:
+            reason_parts.append(f"{total_errors} errors, more than the {expectations.expected_errors} expected")
+        if warnings_over_budget:
+            reason_parts.append(f"{total_warnings} warnings, more than the {expectations.expected_warnings} expected")
+        elif total_warnings > 0:
             reason_parts.append(f"{total_warnings} warnings")
         if clippy_lints > 0:
             reason_parts.append(f"{clippy_lints} clippy lints")
+        if test_result and not tests_ok:
+            if test_result.timed_out:
+                reason_parts.append(f"Tests timed out after {expectations.time_limit or self.timeout} seconds")
+            elif tests_failed:
+                reason_parts.append(f"{tests_failed} of {tests_passed + tests_failed} tests failed")
+            elif any(t.outcome == TestOutcome.FAILED for t in doc_tests):
+                doc_failed = sum(1 for t in doc_tests if t.outcome == TestOutcome.FAILED)
+                doc_run = sum(1 for t in doc_tests if t.outcome in (TestOutcome.PASSED, TestOutcome.FAILED))
+                reason_parts.append(f"{doc_failed} of {doc_run} doc tests failed")
+            else:
+                reason_parts.append("Tests failed to run")
         
         if not reason_parts:
             reason = "Build and clippy passed successfully"
@@ -362,29 +1101,57 @@ class RustBuildScorer(BaseScorer):
         
         metadata = {
             "build_success": build_result.success,
+            "build_status": build_result.status.value,
             "build_errors": build_errors,
             "build_warnings": build_warnings,
             "build_return_code": build_result.return_code,
-            "test_case": {
-                "repo_url": test_case.repo_url,
-                "tag_or_branch": test_case.tag_or_branch,
-                "file_path": test_case.file_path,
-                "description": test_case.description
-            }
+            "diagnostics": diagnostics,
+            "test_case": test_case.to_metadata()
         }
         
+        if regions is not None:
+            metadata["substituted_regions"] = [asdict(region) for region in regions]
+            metadata["outside_diagnostics"] = outside
+        
+        if expectations.to_metadata():
+            metadata["expectations"] = expectations.to_metadata()
+        
         if clippy_result:
             metadata.update({
                 "clippy_success": clippy_result.success,
+                "clippy_status": clippy_result.status.value,
                 "clippy_errors": clippy_errors,
                 "clippy_warnings": clippy_warnings,
                 "clippy_lints": clippy_lints,
+                "clippy_lints_by_group": clippy_lints_by_group,
                 "clippy_return_code": clippy_result.return_code
             })
         
+        if test_result:
+            metadata.update({
+                "test_success": test_result.success,
+                "test_status": test_result.status.value,
+                "tests_passed": tests_passed,
+                "tests_failed": tests_failed,
+                "test_pass_rate": test_pass_rate,
+                "failed_tests": [t.name for t in harness_tests
+                                 if t.outcome == TestOutcome.FAILED and t not in hidden],
+                "test_return_code": test_result.return_code
+            })
+            if doc_tests:
+                metadata["doc_tests_passed"] = sum(1 for t in doc_tests if t.outcome == TestOutcome.PASSED)
+                metadata["doc_tests_failed"] = sum(1 for t in doc_tests if t.outcome == TestOutcome.FAILED)
+                metadata["failed_doc_tests"] = [t.name for t in doc_tests if t.outcome == TestOutcome.FAILED]
+            if hidden_tests:
+                # Counts only; the names and output would give the checks away
+                metadata["hidden_tests_passed"] = sum(1 for t in hidden if t.outcome == TestOutcome.PASSED)
+                metadata["hidden_tests_failed"] = sum(1 for t in hidden if t.outcome == TestOutcome.FAILED)
+        elif tests_not_run:
+            metadata.update({"tests_not_run": True, "test_pass_rate": test_pass_rate})
+        
         return ScorerResult(
             score=score,
             passed=passed,
             reason=reason,
             metadata=metadata
-        )
\ No newline at end of file
+        )
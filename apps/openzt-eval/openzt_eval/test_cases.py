@@ -0,0 +1,291 @@
+"""Test cases of the Rust scorers, read from the expected JSON of an evaluation case."""
+
+from typing import Any, Dict, List, Optional
+from dataclasses import dataclass, asdict, fields
+from pathlib import Path
+import re
+
+# Package names cargo new accepts
+CRATE_NAME = re.compile(r'^[A-Za-z_][A-Za-z0-9_-]*$')
+
+
+@dataclass
+class RustBuildSlot:
+    """A single substitution point in a Rust build test case.
+    
+    The point is either a replacement_target string, or an item_path such as
+    "Stack::pop" naming a function whose whole item or body is replaced
+    (see rust_syntax.substitute_item for the replace modes).
+    """
+    name: str
+    file_path: str
+    replacement_target: Optional[str] = None
+    description: Optional[str] = None
+    item_path: Optional[str] = None
+    replace: str = "auto"
+    
+    def __post_init__(self):
+        if bool(self.replacement_target) == bool(self.item_path):
+            raise ValueError(f"Slot '{self.name}' needs exactly one of replacement_target or item_path")
+        if self.replace not in ("auto", "item", "body"):
+            raise ValueError(f"Slot '{self.name}' has unknown replace mode: {self.replace}")
+
+
+@dataclass
+class CaseExpectations:
+    """Per-case limits and scoring overrides, read from an eval case's metadata.
+    
+    Unset values fall back to the scorer's configuration.
+    """
+    expected_errors: Optional[int] = None  # more errors than this fail the case
+    expected_warnings: Optional[int] = None  # more warnings than this fail the case, even if allowed
+    time_limit: Optional[float] = None  # seconds the tests or the program may run
+    allow_warnings: Optional[bool] = None
+    error_penalty: Optional[float] = None
+    warning_penalty: Optional[float] = None
+    clippy_penalty: Optional[float] = None
+    
+    def __post_init__(self):
+        for field_ in fields(self):
+            value = getattr(self, field_.name)
+            if value is None:
+                continue
+            if field_.name == "allow_warnings":
+                if not isinstance(value, bool):
+                    raise ValueError(f"allow_warnings must be true or false, got {value!r}")
+            elif isinstance(value, bool) or not isinstance(value, (int, float)) or value < 0:
+                raise ValueError(f"{field_.name} must be a non-negative number, got {value!r}")
+    
+    @classmethod
+    def from_metadata(cls, metadata: Optional[Dict[str, Any]]) -> 'CaseExpectations':
+        """Pick the expectations out of a case's metadata, ignoring its other keys."""
+        names = {field_.name for field_ in fields(cls)}
+        return cls(**{key: value for key, value in (metadata or {}).items() if key in names})
+    
+    def to_metadata(self) -> Dict[str, Any]:
+        return {key: value for key, value in asdict(self).items() if value is not None}
+
+
+@dataclass
+class HiddenTest:
+    """A test the model never sees, added to the crate after its answer is built.
+    
+    A file_path under tests/ is written as an integration test against the
+    crate's public API; it must not exist yet. Any other file_path must be an
+    existing source file, to which the content is appended as the body of a
+    #[cfg(test)] module, so it can reach private items through `use super::*`.
+    """
+    file_path: str
+    content: Optional[str] = None
+    source: Optional[str] = None  # file holding the content, resolved like local_path
+    module: str = "hidden_tests"  # name of the appended module
+    
+    def __post_init__(self):
+        if bool(self.content) == bool(self.source):
+            raise ValueError(f"Hidden test {self.file_path} needs exactly one of content or source")
+    
+    @property
+    def integration(self) -> bool:
+        return Path(self.file_path).parts[0] == "tests"
+    
+    def covers(self, test: Any) -> bool:
+        """Whether a TestResult of cargo test comes from this hidden test."""
+        if self.integration:
+            return test.suite is not None and Path(test.suite).as_posix() == Path(self.file_path).as_posix()
+        return f"::{self.module}::" in f"::{test.name}" and (test.suite or "").startswith("unittests ")
+
+
+@dataclass
+class RustBuildTestCase:
+    """Test case for Rust build evaluation.
+    
+    The crate under test comes either from a git repository (repo_url and
+    tag_or_branch) or from a local crate directory (local_path), which is
+    copied into a temporary directory before substitution.
+    
+    A case substitutes either a single file_path/replacement_target (or
+    file_path/item_path) pair or several named slots, in which case the
+    model's answer is split across them.
+    
+    With response_format "patch" there are no substitution points: the model
+    fixes the crate by answering with a unified diff, applied with up to
+    `fuzz` lines of context ignored, or with whole files labelled by path.
+    Such an answer may not touch cargo configuration, tests/ or the files
+    hidden tests go into, nor Cargo.toml and build.rs unless
+    allow_manifest_changes is set.
+    
+    hidden_tests are added after the answer has been built and linted, and
+    run along with the crate's own tests; their names and output are kept
+    out of what is reported back to the model.
+    """
+    file_path: Optional[str] = None
+    replacement_target: Optional[str] = None
+    repo_url: Optional[str] = None
+    tag_or_branch: Optional[str] = None
+    local_path: Optional[str] = None
+    description: Optional[str] = None
+    slots: Optional[List[RustBuildSlot]] = None
+    item_path: Optional[str] = None
+    replace: str = "auto"
+    hidden_tests: Optional[List[HiddenTest]] = None
+    response_format: str = "substitution"  # or "patch"
+    fuzz: int = 2
+    allow_manifest_changes: bool = False
+    
+    def __post_init__(self):
+        if bool(self.repo_url) == bool(self.local_path):
+            raise ValueError("Exactly one of repo_url or local_path must be set")
+        if self.response_format not in ("substitution", "patch"):
+            raise ValueError(f"Unknown response_format: {self.response_format}")
+        if self.fuzz < 0:
+            raise ValueError(f"fuzz must be non-negative, got {self.fuzz}")
+        
+        if self.hidden_tests:
+            self.hidden_tests = [
+                test if isinstance(test, HiddenTest) else HiddenTest(**test)
+                for test in self.hidden_tests
+            ]
+        
+        if self.response_format == "patch":
+            if self.slots or self.file_path or self.replacement_target or self.item_path:
+                raise ValueError("Patch cases have no slots, file_path, replacement_target or item_path")
+        elif self.slots:
+            if self.file_path or self.replacement_target or self.item_path:
+                raise ValueError("file_path, replacement_target and item_path cannot be combined with slots")
+            self.slots = [
+                slot if isinstance(slot, RustBuildSlot) else RustBuildSlot(**slot)
+                for slot in self.slots
+            ]
+            names = [slot.name for slot in self.slots]
+            if len(set(names)) != len(names):
+                raise ValueError(f"Duplicate slot names: {names}")
+        elif not self.file_path:
+            raise ValueError("Either slots or file_path with replacement_target or item_path must be set")
+        else:
+            # Validates the target the same way as for slots
+            self.get_slots()
+    
+    def get_slots(self) -> List[RustBuildSlot]:
+        """Get the substitution slots, treating a single target as one slot."""
+        if self.response_format == "patch":
+            return []
+        if self.slots:
+            return self.slots
+        return [RustBuildSlot(
+            name="main",
+            file_path=self.file_path,
+            replacement_target=self.replacement_target,
+            description=self.description,
+            item_path=self.item_path,
+            replace=self.replace
+        )]
+    
+    def to_metadata(self) -> Dict[str, Any]:
+        """What identifies the case in a result's metadata."""
+        return {
+            "repo_url": self.repo_url,
+            "tag_or_branch": self.tag_or_branch,
+            "local_path": self.local_path,
+            "file_path": self.file_path,
+            "item_path": self.item_path,
+            "slots": [slot.name for slot in self.slots] if self.slots else None,
+            "response_format": self.response_format,
+            "description": self.description
+        }
+
+
+@dataclass
+class RustRunTestCase:
+    """Test case configuration for running a complete Rust program.
+    
+    The model's answer replaces file_path as a whole; the binary is then
+    built and run, and its stdout compared against expected_stdout.
+    """
+    expected_stdout: str
+    repo_url: Optional[str] = None
+    tag_or_branch: Optional[str] = None
+    local_path: Optional[str] = None
+    file_path: str = "src/main.rs"
+    bin: Optional[str] = None
+    args: Optional[List[str]] = None
+    stdin: Optional[str] = None
+    expected_exit_code: int = 0
+    timeout: Optional[float] = None
+    description: Optional[str] = None
+    
+    def __post_init__(self):
+        if bool(self.repo_url) == bool(self.local_path):
+            raise ValueError("Exactly one of repo_url or local_path must be set")
+
+
+@dataclass
+class RustCleanupTestCase:
+    """Test case configuration for removing the diagnostics of a crate without changing its behavior.
+    
+    The model rewrites file_path, as a whole file or a unified diff. The
+    program (if the crate has one) is run before and after with args and
+    stdin, and the crate's tests must still pass.
+    """
+    repo_url: Optional[str] = None
+    tag_or_branch: Optional[str] = None
+    local_path: Optional[str] = None
+    file_path: str = "src/main.rs"
+    run: Optional[bool] = None  # compare the program's output; default: if the crate has a binary
+    bin: Optional[str] = None
+    args: Optional[List[str]] = None
+    stdin: Optional[str] = None
+    timeout: Optional[float] = None
+    fuzz: int = 2
+    description: Optional[str] = None
+    
+    def __post_init__(self):
+        if bool(self.repo_url) == bool(self.local_path):
+            raise ValueError("Exactly one of repo_url or local_path must be set")
+
+
+@dataclass
+class RustCrateTestCase:
+    """Test case configuration for generating a whole crate from a spec.
+    
+    The crate starts out as the scaffold `cargo new` would make for package
+    `name`: a Cargo.toml and a hello-world src/main.rs, or an empty src/lib.rs
+    for a "lib" crate. The model answers with whole files labelled with their
+    path, Cargo.toml and files under src/, which replace or add to the
+    scaffold. Dependencies must come from crates.io and be listed in
+    allowed_dependencies.
+    
+    hidden_tests are added after the crate has been built, as for
+    RustBuildTestCase. Integration tests of a binary can run it through
+    env!("CARGO_BIN_EXE_<name>").
+    """
+    name: str = "solution"
+    kind: str = "bin"  # or "lib"
+    edition: str = "2021"
+    allowed_dependencies: Optional[List[str]] = None
+    hidden_tests: Optional[List[HiddenTest]] = None
+    description: Optional[str] = None
+    
+    def __post_init__(self):
+        if self.kind not in ("bin", "lib"):
+            raise ValueError(f"Unknown crate kind: {self.kind}")
+        if not CRATE_NAME.match(self.name):
+            raise ValueError(f"Invalid package name: {self.name!r}")
+        if self.hidden_tests:
+            self.hidden_tests = [
+                test if isinstance(test, HiddenTest) else HiddenTest(**test)
+                for test in self.hidden_tests
+            ]
+    
+    @property
+    def main_file(self) -> str:
+        return "src/main.rs" if self.kind == "bin" else "src/lib.rs"
+    
+    def to_metadata(self) -> Dict[str, Any]:
+        """What identifies the case in a result's metadata."""
+        return {
+            "name": self.name,
+            "kind": self.kind,
+            "edition": self.edition,
+            "allowed_dependencies": self.allowed_dependencies or [],
+            "description": self.description
+        }
@@ -0,0 +1,114 @@
+"""Checks that Rust build cases can be solved, and aren't solved by a placeholder answer."""
+
+from dataclasses import dataclass, field
+from typing import Any, Dict, List, Optional, Union
+import json
+
+from .evaluator import EvalCase
+from .repositories import CheckoutError
+from .scorers import RustBuildScorer, ScorerResult
+from .test_cases import CaseExpectations, RustBuildTestCase
+
+# What a model that gives up would answer; it must never pass
+PLACEHOLDER = "todo!()"
+
+
+@dataclass
+class CaseValidation:
+    """Outcome of validating one case: a problem per way in which it is broken."""
+    name: str
+    problems: List[str] = field(default_factory=list)
+    results: Dict[str, ScorerResult] = field(default_factory=dict)  # by answer: reference, empty, placeholder
+
+    @property
+    def ok(self) -> bool:
+        return not self.problems
+
+    def to_dict(self) -> Dict[str, Any]:
+        return {
+            "case": self.name,
+            "ok": self.ok,
+            "problems": self.problems,
+            "results": {
+                answer: {"passed": result.passed, "score": result.score, "reason": result.reason}
+                for answer, result in self.results.items()
+            },
+        }
+
+
+def answer_for_slots(answer: Union[str, Dict[str, str]], test_case: RustBuildTestCase) -> str:
+    """Write an answer the way a model would: slot answers as a JSON object keyed by slot name."""
+    if isinstance(answer, dict):
+        return json.dumps(answer)
+    if test_case.slots:
+        return json.dumps({slot.name: answer for slot in test_case.slots})
+    return answer
+
+
+def validate_case(scorer: RustBuildScorer, case: EvalCase) -> CaseValidation:
+    """
+    Check that a case is solvable and that it tells a solution from a non-answer.
+
+    The case's substitution targets must exist, its reference_solution must
+    pass the scorer, and both an empty answer and a todo!() in every slot must
+    fail it. Patch cases have no slots, so only the empty answer is tried.
+
+    Args:
+        scorer: The scorer the case is meant to be evaluated with
+        case: The case; its expected value is the RustBuildTestCase JSON
+
+    Returns:
+        The validation, with no problems if the case is sound
+    """
+    validation = CaseValidation(name=case.name)
+    if not case.expected:
+        validation.problems.append("No test case configuration")
+        return validation
+
+    try:
+        test_case = RustBuildTestCase(**json.loads(case.expected))
+        CaseExpectations.from_metadata(case.metadata)
+    except (json.JSONDecodeError, TypeError, ValueError) as e:
+        validation.problems.append(f"Invalid test case configuration: {e}")
+        return validation
+
+    def score(answer: str, response: str) -> Optional[ScorerResult]:
+        try:
+            result = scorer.score(case.prompt, response, case.expected, case.metadata)
+        except CheckoutError as e:
+            validation.problems.append(f"Checkout failed: {e}")
+            return None
+        validation.results[answer] = result
+        return result
+
+    if test_case.response_format != "patch":
+        # Every slot is answered, so anything that stops the build or the tests is the case's fault
+        placeholder = score("placeholder", answer_for_slots(PLACEHOLDER, test_case))
+        if placeholder is None:
+            return validation
+        metadata = placeholder.metadata or {}
+        if "slot_errors" in metadata or "build_status" not in metadata or metadata.get("tests_not_run"):
+            validation.problems.append(f"Cannot be evaluated: {placeholder.reason}")
+            return validation
+        if placeholder.passed:
+            validation.problems.append(f"A {PLACEHOLDER} answer passes")
+
+    empty = score("empty", "")
+    if empty is None:
+        return validation
+    if empty.passed:
+        validation.problems.append("An empty answer passes")
+
+    if case.reference_solution is None:
+        validation.problems.append("No reference_solution")
+    else:
+        reference = score("reference", answer_for_slots(case.reference_solution, test_case))
+        if reference is not None and not reference.passed:
+            validation.problems.append(f"Reference solution fails: {reference.reason}")
+
+    return validation
+
+
+def validate_cases(scorer: RustBuildScorer, cases: List[EvalCase]) -> List[CaseValidation]:
+    """Validate every case in turn."""
+    return [validate_case(scorer, case) for case in cases]
@@ -5,8 +5,33 @@ This library provides a simple interface to run cargo build commands with variou
 options and parse the resulting errors and warnings.
 """
 
-from .builder import CargoBuilder, BuildResult, BuildMessage
-from .parser import CargoOutputParser
+from .builder import CargoBuilder, BuildResult, BuildMessage, RunResult, BuildStatus, ResourceLimits
+from .parser import CargoOutputParser, TestResult, TestOutcome, Artifact, BuildScriptOutput
+from .lints import LintRegistry, LintInfo, LintLevel
+from .fixes import apply_suggestions, FixResult
+from .cache import TargetDirPool, TemplateCache
+from .toolchain import ToolchainInfo, toolchain_info
 
 __version__ = "0.1.0"
-__all__ = ["CargoBuilder", "BuildResult", "BuildMessage", "CargoOutputParser"]
\ No newline at end of file
+__all__ = [
+    "CargoBuilder",
+    "BuildResult",
+    "BuildMessage",
+    "RunResult",
+    "BuildStatus",
+    "ResourceLimits",
+    "CargoOutputParser",
+    "TestResult",
+    "TestOutcome",
+    "Artifact",
+    "BuildScriptOutput",
+    "LintRegistry",
+    "LintInfo",
+    "LintLevel",
+    "apply_suggestions",
+    "FixResult",
+    "TargetDirPool",
+    "TemplateCache",
+    "ToolchainInfo",
+    "toolchain_info",
+]
\ No newline at end of file
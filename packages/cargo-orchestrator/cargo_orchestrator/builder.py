@@ -1,11 +1,15 @@
+import os
+import signal
 import subprocess
 import json
+import time
 from pathlib import Path
-from typing import Optional, List, Dict, Any
-from dataclasses import dataclass
+from typing import Optional, List, Dict, Any, Tuple
+from dataclasses import dataclass, field
 from enum import Enum
 
-from .parser import CargoOutputParser, BuildMessage
+from .parser import CargoOutputParser, BuildMessage, TestResult, Artifact, BuildScriptOutput
+from .toolchain import ToolchainInfo, toolchain_info
 
 
 class BuildProfile(Enum):
@@ -13,6 +17,41 @@ class BuildProfile(Enum):
     RELEASE = "release"
 
 
+class BuildStatus(Enum):
+    SUCCEEDED = "succeeded"
+    FAILED = "failed"
+    TIMED_OUT = "timed_out"
+
+
+@dataclass
+class ResourceLimits:
+    """
+    POSIX resource limits applied to cargo and everything it spawns.
+    
+    The limits are set per process (cargo, each rustc, build scripts, test
+    binaries), not for the build as a whole.
+    """
+    memory_bytes: Optional[int] = None  # RLIMIT_AS, i.e. address space
+    cpu_seconds: Optional[int] = None  # RLIMIT_CPU; exceeding it kills with SIGXCPU
+    
+    def wrap(self, cmd: List[str]) -> List[str]:
+        """
+        Run a command under the limits, set by a shell that then execs it.
+        
+        Setting them in the forked child with preexec_fn isn't safe while other
+        threads run, e.g. builds started from a thread pool.
+        """
+        ulimits = []
+        if self.memory_bytes is not None:
+            ulimits.append(f"ulimit -v {self.memory_bytes // 1024}")
+        if self.cpu_seconds is not None:
+            ulimits.append(f"ulimit -t {self.cpu_seconds}")
+        if not ulimits:
+            return cmd
+        script = " && ".join(ulimits + ['exec "$@"'])
+        return ["/bin/sh", "-c", script, "sh"] + list(cmd)
+
+
 @dataclass
 class BuildResult:
     """Result of a cargo build operation."""
@@ -21,6 +60,55 @@ class BuildResult:
     stdout: str
     stderr: str
     return_code: int
+    tests: List[TestResult] = field(default_factory=list)
+    status: Optional[BuildStatus] = None
+    fixes: Dict[str, int] = field(default_factory=dict)
+    artifacts: List[Artifact] = field(default_factory=list)
+    build_scripts: List[BuildScriptOutput] = field(default_factory=list)
+    build_finished: Optional[bool] = None
+    toolchain: Optional[ToolchainInfo] = None
+    
+    def __post_init__(self):
+        if self.status is None:
+            self.status = BuildStatus.SUCCEEDED if self.success else BuildStatus.FAILED
+    
+    @property
+    def timed_out(self) -> bool:
+        return self.status == BuildStatus.TIMED_OUT
+    
+    @property
+    def executables(self) -> List[Path]:
+        """Paths of the executables built, including test harnesses built by cargo test."""
+        return [Path(a.executable) for a in self.artifacts if a.executable]
+    
+    def find_executable(self, name: str) -> Optional[Path]:
+        """
+        Find the path of a built binary by target name.
+        
+        Args:
+            name: Name of the bin target (e.g., 'success_project').
+            
+        Returns:
+            Path to the executable, or None if it wasn't built.
+        """
+        for artifact in self.artifacts:
+            if artifact.executable and artifact.target_name == name and "bin" in artifact.kinds and not artifact.test:
+                return Path(artifact.executable)
+        return None
+
+
+@dataclass
+class RunResult:
+    """Result of building and running a binary."""
+    success: bool
+    build: BuildResult
+    stdout: str = ""
+    stderr: str = ""
+    exit_code: Optional[int] = None
+    timed_out: bool = False
+    duration: Optional[float] = None
+    executable: Optional[Path] = None
+    error: Optional[str] = None
 
 
 class CargoBuilder:
@@ -33,6 +121,9 @@ class CargoBuilder:
         target: Optional[str] = None,
         profile: BuildProfile = BuildProfile.DEBUG,
         use_nightly: bool = False,
+        timeout: Optional[float] = None,
+        limits: Optional[ResourceLimits] = None,
+        target_dir: Optional[Path] = None,
     ):
         """
         Initialize a CargoBuilder instance.
@@ -43,14 +134,27 @@ class CargoBuilder:
             target: Target triple to build for (e.g., 'x86_64-unknown-linux-gnu').
             profile: Build profile (debug or release).
             use_nightly: Whether to use nightly toolchain.
+            timeout: Wall-clock seconds each cargo command may take before cargo
+                and all its child processes (rustc, build scripts, tests) are killed.
+            limits: Resource limits for cargo and its child processes (POSIX only).
+            target_dir: Directory for build output instead of the crate's target/,
+                e.g. one kept warm across checkouts of the same repository.
         """
         self.root_dir = root_dir or Path.cwd()
         self.manifest_path = manifest_path
         self.target = target
         self.profile = profile
         self.use_nightly = use_nightly
+        self.timeout = timeout
+        self.limits = limits
+        self.target_dir = target_dir
         self.parser = CargoOutputParser()
     
+    @property
+    def toolchain(self) -> ToolchainInfo:
+        """Versions of rustc, cargo and clippy used in root_dir, detected once per session."""
+        return toolchain_info(self.root_dir, self.use_nightly)
+    
     def build(
         self,
         features: Optional[List[str]] = None,
@@ -89,41 +193,7 @@ class CargoBuilder:
             use_clippy=use_clippy,
         )
         
-        try:
-            process = subprocess.Popen(
-                cmd,
-                cwd=self.root_dir,
-                stdout=subprocess.PIPE,
-                stderr=subprocess.PIPE,
-                text=True,
-            )
-            
-            stdout, stderr = process.communicate()
-            
-            # Parse messages if using JSON format
-            messages = []
-            if message_format == "json":
-                messages = self.parser.parse_json_output(stdout)
-            else:
-                # For human-readable output, parse from stderr
-                messages = self.parser.parse_human_output(stderr)
-            
-            return BuildResult(
-                success=process.returncode == 0,
-                messages=messages,
-                stdout=stdout,
-                stderr=stderr,
-                return_code=process.returncode,
-            )
-            
-        except Exception as e:
-            return BuildResult(
-                success=False,
-                messages=[],
-                stdout="",
-                stderr=str(e),
-                return_code=-1,
-            )
+        return self._run_cargo(cmd, message_format)
     
     def clippy(
         self,
@@ -163,6 +233,342 @@ class CargoBuilder:
             use_clippy=True,
         )
     
+    def test(
+        self,
+        features: Optional[List[str]] = None,
+        all_features: bool = False,
+        no_default_features: bool = False,
+        package: Optional[str] = None,
+        workspace: bool = False,
+        message_format: str = "json",
+        extra_args: Optional[List[str]] = None,
+        test_args: Optional[List[str]] = None,
+        test_format: str = "human",
+    ) -> BuildResult:
+        """
+        Run cargo test with the specified options.
+        
+        Compiler messages are parsed as for build(), and the libtest output
+        is parsed into per-test results available as BuildResult.tests.
+        
+        Args:
+            features: List of features to enable.
+            all_features: Enable all features.
+            no_default_features: Disable default features.
+            package: Specific package to test in a workspace.
+            workspace: Test all packages in the workspace.
+            message_format: Output format for compiler messages ('json' or 'human').
+            extra_args: Additional arguments to pass to cargo test.
+            test_args: Arguments passed to the test harness after '--'.
+            test_format: libtest output format ('human' or 'json'). The JSON
+                format is unstable and requires use_nightly.
+            
+        Returns:
+            BuildResult containing success status, parsed messages, test results, and raw output.
+        """
+        harness_args = list(test_args or [])
+        if test_format == "json":
+            harness_args.extend(["-Z", "unstable-options", "--format", "json"])
+        
+        cmd = self._build_command(
+            features=features,
+            all_features=all_features,
+            no_default_features=no_default_features,
+            package=package,
+            workspace=workspace,
+            message_format=message_format,
+            extra_args=extra_args,
+            subcommand="test",
+            test_args=harness_args,
+        )
+        
+        result = self._run_cargo(cmd, message_format)
+        # libtest always reports on stdout, whatever the compiler message format
+        result.tests = self.parser.parse_test_output(
+            result.stdout, self.parser.parse_test_suites(result.stderr)
+        )
+        return result
+    
+    def run(
+        self,
+        bin: Optional[str] = None,
+        args: Optional[List[str]] = None,
+        stdin: Optional[str] = None,
+        timeout: Optional[float] = None,
+        env: Optional[Dict[str, str]] = None,
+        features: Optional[List[str]] = None,
+        all_features: bool = False,
+        no_default_features: bool = False,
+        package: Optional[str] = None,
+        extra_args: Optional[List[str]] = None,
+    ) -> RunResult:
+        """
+        Build a binary and run it, capturing its output.
+        
+        Unlike cargo run, the binary is run directly (located via the
+        compiler-artifact messages), so stdout and stderr contain only the
+        program's own output.
+        
+        Args:
+            bin: Name of the bin target to run. Required if the package has several.
+            args: Arguments passed to the program.
+            stdin: Text fed to the program's standard input.
+            timeout: Seconds after which the program is killed (the build itself
+                is limited by the builder's timeout).
+            env: Extra environment variables for the program.
+            features: List of features to enable.
+            all_features: Enable all features.
+            no_default_features: Disable default features.
+            package: Specific package to build in a workspace.
+            extra_args: Additional arguments to pass to cargo build.
+            
+        Returns:
+            RunResult with the build result and the program's output and exit code.
+        """
+        build_args = list(extra_args or [])
+        if bin:
+            build_args.extend(["--bin", bin])
+        
+        build_result = self.build(
+            features=features,
+            all_features=all_features,
+            no_default_features=no_default_features,
+            package=package,
+            extra_args=build_args,
+        )
+        if not build_result.success:
+            return RunResult(success=False, build=build_result, error="Build failed")
+        
+        if bin:
+            executable = build_result.find_executable(bin)
+        else:
+            binaries = [
+                Path(a.executable) for a in build_result.artifacts
+                if a.executable and "bin" in a.kinds and not a.test
+            ]
+            executable = binaries[0] if len(binaries) == 1 else None
+            if len(binaries) > 1:
+                return RunResult(
+                    success=False,
+                    build=build_result,
+                    error=f"Several binaries were built, choose one with bin: {[b.name for b in binaries]}",
+                )
+        
+        if executable is None:
+            return RunResult(success=False, build=build_result, error="No binary was built")
+        
+        process_env = None
+        if env:
+            process_env = dict(os.environ)
+            process_env.update(env)
+        
+        start = time.monotonic()
+        return_code, stdout, stderr, timed_out = self._execute(
+            [str(executable)] + list(args or []),
+            stdin=stdin,
+            timeout=timeout,
+            env=process_env,
+        )
+        
+        return RunResult(
+            success=return_code == 0 and not timed_out,
+            build=build_result,
+            stdout=stdout,
+            stderr=stderr,
+            exit_code=None if timed_out else return_code,
+            timed_out=timed_out,
+            duration=time.monotonic() - start,
+            executable=executable,
+            error=f"Timed out after {timeout} seconds" if timed_out else None,
+        )
+    
+    def metadata(self, no_deps: bool = True, extra_args: Optional[List[str]] = None) -> Dict[str, Any]:
+        """
+        Run cargo metadata and return the parsed workspace description.
+        
+        Manifests are read as cargo reads them, so dependencies come out
+        normalized: renamed ones carry their package name and a rename,
+        target-specific and dev- or build-dependencies their target and kind.
+        
+        Args:
+            no_deps: Describe only the workspace members, without resolving
+                dependencies (which needs no network or lock file).
+            extra_args: Additional arguments to pass to cargo metadata.
+            
+        Returns:
+            The JSON output of cargo metadata --format-version 1.
+            
+        Raises:
+            RuntimeError: If cargo rejects the manifest or times out.
+        """
+        cmd = ["cargo", "+nightly", "metadata"] if self.use_nightly else ["cargo", "metadata"]
+        cmd.extend(["--format-version", "1"])
+        if self.manifest_path:
+            cmd.extend(["--manifest-path", str(self.manifest_path)])
+        if no_deps:
+            cmd.append("--no-deps")
+        cmd.extend(extra_args or [])
+        
+        return_code, stdout, stderr, timed_out = self._execute(cmd, timeout=self.timeout)
+        if timed_out:
+            raise RuntimeError(f"cargo metadata timed out after {self.timeout} seconds")
+        if return_code != 0:
+            raise RuntimeError(stderr.strip() or f"cargo metadata exited with {return_code}")
+        return json.loads(stdout)
+    
+    def fix(
+        self,
+        use_clippy: bool = False,
+        features: Optional[List[str]] = None,
+        all_features: bool = False,
+        no_default_features: bool = False,
+        package: Optional[str] = None,
+        workspace: bool = False,
+        message_format: str = "json",
+        extra_args: Optional[List[str]] = None,
+        broken_code: bool = False,
+    ) -> BuildResult:
+        """
+        Run cargo fix, or cargo clippy --fix, to apply machine-applicable suggestions.
+        
+        The source files under root_dir are modified in place. Uncommitted
+        changes and missing version control are allowed, since fixes are
+        usually applied to scratch copies.
+        
+        Args:
+            use_clippy: Apply clippy's suggestions (cargo clippy --fix) instead of rustc's.
+            features: List of features to enable.
+            all_features: Enable all features.
+            no_default_features: Disable default features.
+            package: Specific package to fix in a workspace.
+            workspace: Fix all packages in the workspace.
+            message_format: Output format ('json' or 'human').
+            extra_args: Additional arguments to pass to cargo fix.
+            broken_code: Keep fixes even if they leave the code failing to compile.
+            
+        Returns:
+            BuildResult with the messages that remain after fixing, and the
+            number of fixes applied per file in BuildResult.fixes.
+        """
+        fix_args = ["--allow-dirty", "--allow-staged", "--allow-no-vcs"]
+        if use_clippy:
+            fix_args.insert(0, "--fix")
+        if broken_code:
+            fix_args.append("--broken-code")
+        
+        cmd = self._build_command(
+            features=features,
+            all_features=all_features,
+            no_default_features=no_default_features,
+            package=package,
+            workspace=workspace,
+            message_format=message_format,
+            extra_args=fix_args + list(extra_args or []),
+            subcommand="clippy" if use_clippy else "fix",
+        )
+        
+        result = self._run_cargo(cmd, message_format)
+        # cargo reports the applied fixes on stderr in both message formats
+        result.fixes = self.parser.parse_fix_output(result.stderr)
+        return result
+    
+    def _run_cargo(self, cmd: List[str], message_format: str) -> BuildResult:
+        """Run a cargo command and parse its compiler messages."""
+        try:
+            return_code, stdout, stderr, timed_out = self._execute(cmd, timeout=self.timeout)
+            
+            # Parse messages if using JSON format
+            messages = []
+            if message_format == "json":
+                messages = self.parser.parse_json_output(stdout)
+            else:
+                # For human-readable output, parse from stderr
+                messages = self.parser.parse_human_output(stderr)
+            
+            if timed_out:
+                status = BuildStatus.TIMED_OUT
+            else:
+                status = BuildStatus.SUCCEEDED if return_code == 0 else BuildStatus.FAILED
+            
+            result = BuildResult(
+                success=status == BuildStatus.SUCCEEDED,
+                messages=messages,
+                stdout=stdout,
+                stderr=stderr,
+                return_code=return_code,
+                status=status,
+                toolchain=self.toolchain,
+            )
+            
+            # Artifacts and build-finished are only reported in JSON format
+            if message_format == "json":
+                result.artifacts = self.parser.parse_artifacts(stdout)
+                result.build_scripts = self.parser.parse_build_scripts(stdout)
+                result.build_finished = self.parser.parse_build_finished(stdout)
+            
+            return result
+            
+        except Exception as e:
+            return BuildResult(
+                success=False,
+                messages=[],
+                stdout="",
+                stderr=str(e),
+                return_code=-1,
+                toolchain=self.toolchain,
+            )
+    
+    def _execute(
+        self,
+        cmd: List[str],
+        stdin: Optional[str] = None,
+        timeout: Optional[float] = None,
+        env: Optional[Dict[str, str]] = None,
+    ) -> Tuple[int, str, str, bool]:
+        """
+        Run a command with the builder's resource limits and kill it on timeout.
+        
+        The command runs in its own process group, so that on timeout every
+        process it spawned is killed with it; killing only cargo would leave
+        rustc or a hanging build script running.
+        
+        Returns:
+            Tuple of return code, stdout, stderr and whether it timed out.
+        """
+        posix = os.name == "posix"
+        if self.limits and posix:
+            cmd = self.limits.wrap(cmd)
+        process = subprocess.Popen(
+            cmd,
+            cwd=self.root_dir,
+            stdin=subprocess.PIPE if stdin is not None else subprocess.DEVNULL,
+            stdout=subprocess.PIPE,
+            stderr=subprocess.PIPE,
+            text=True,
+            env=env,
+            start_new_session=posix,
+        )
+        
+        try:
+            stdout, stderr = process.communicate(input=stdin, timeout=timeout)
+            return process.returncode, stdout, stderr, False
+        except subprocess.TimeoutExpired:
+            if posix:
+                try:
+                    os.killpg(process.pid, signal.SIGKILL)
+                except ProcessLookupError:
+                    pass
+            else:
+                process.kill()
+            # Collect whatever was written before the kill
+            try:
+                stdout, stderr = process.communicate(timeout=5)
+            except subprocess.TimeoutExpired:
+                # A process that left the group still holds the pipes open
+                process.wait()
+                stdout, stderr = "", ""
+            return process.returncode, stdout, stderr, True
+    
     def _build_command(
         self,
         features: Optional[List[str]] = None,
@@ -173,24 +579,27 @@ class CargoBuilder:
         message_format: str = "json",
         extra_args: Optional[List[str]] = None,
         use_clippy: bool = False,
+        subcommand: Optional[str] = None,
+        test_args: Optional[List[str]] = None,
     ) -> List[str]:
         """Build the cargo command with all specified options."""
+        if subcommand is None:
+            subcommand = "clippy" if use_clippy else "build"
+        
         # Start with cargo or cargo +nightly
         if self.use_nightly:
-            if use_clippy:
-                cmd = ["cargo", "+nightly", "clippy"]
-            else:
-                cmd = ["cargo", "+nightly", "build"]
+            cmd = ["cargo", "+nightly", subcommand]
         else:
-            if use_clippy:
-                cmd = ["cargo", "clippy"]
-            else:
-                cmd = ["cargo", "build"]
+            cmd = ["cargo", subcommand]
         
         # Add manifest path if specified
         if self.manifest_path:
             cmd.extend(["--manifest-path", str(self.manifest_path)])
         
+        # Add target directory if specified
+        if self.target_dir:
+            cmd.extend(["--target-dir", str(self.target_dir)])
+        
         # Add target if specified
         if self.target:
             cmd.extend(["--target", self.target])
@@ -222,4 +631,9 @@ class CargoBuilder:
         if extra_args:
             cmd.extend(extra_args)
         
+        # Arguments for the test harness go after the separator
+        if test_args:
+            cmd.append("--")
+            cmd.extend(test_args)
+        
         return cmd
@@ -0,0 +1,254 @@
+import hashlib
+import os
+import re
+import shutil
+import subprocess
+import threading
+from contextlib import contextmanager
+from pathlib import Path
+from typing import Callable, Dict, Iterator, List, Optional
+
+try:
+    import fcntl
+except ImportError:  # Windows; only threads of one process are then serialized
+    fcntl = None
+
+
+def _key_dir(root: Path, key: str) -> Path:
+    """A directory name for an arbitrary key such as "https://host/repo@<sha>"."""
+    return root / hashlib.sha1(key.encode('utf-8')).hexdigest()[:16]
+
+
+class KeyedLock:
+    """A lock per key, held across threads and, through a lock file, across processes."""
+
+    def __init__(self, root: Path):
+        self.root = root
+        self._locks: Dict[str, threading.Lock] = {}
+        self._guard = threading.Lock()
+
+    @contextmanager
+    def hold(self, name: str, blocking: bool = True) -> Iterator[bool]:
+        """Hold the lock called name; yields False if blocking is off and it is taken."""
+        with self._guard:
+            lock = self._locks.setdefault(name, threading.Lock())
+        if not lock.acquire(blocking):
+            yield False
+            return
+        try:
+            self.root.mkdir(parents=True, exist_ok=True)
+            with open(self.root / f"{name}.lock", "w") as lock_file:
+                if fcntl:
+                    try:
+                        fcntl.flock(lock_file, fcntl.LOCK_EX | (0 if blocking else fcntl.LOCK_NB))
+                    except BlockingIOError:
+                        yield False
+                        return
+                yield True
+        finally:
+            lock.release()
+
+
+class TargetDirPool:
+    """
+    Warm target directories shared by builds of the same repository.
+
+    Dependencies compiled into a pooled directory are reused by later builds of
+    any checkout of the repository, so only the workspace crates recompile.
+    Cargo itself serializes builds that share a target directory, so the pool
+    partitions instead: each repository gets up to `slots` directories, and a
+    build holds one exclusively for as long as it needs it.
+    """
+
+    def __init__(self, root: Path, slots: int = 1):
+        if slots < 1:
+            raise ValueError("slots must be at least 1")
+        self.root = Path(root)
+        self.slots = slots
+        self._locks = KeyedLock(self.root)
+        self._slot_available = threading.Condition()
+
+    @contextmanager
+    def acquire(self, key: str) -> Iterator[Path]:
+        """
+        Hold a target directory for the repository identified by key.
+
+        Args:
+            key: Identifies the repository, e.g. its URL or local path
+
+        Yields:
+            The target directory, to pass as CargoBuilder(target_dir=...)
+        """
+        base = _key_dir(self.root, key)
+        while True:
+            # Prefer the first free slot, so a light load keeps reusing the warmest directory
+            for slot in range(self.slots):
+                name = f"{base.name}-{slot}"
+                with self._locks.hold(name, blocking=False) as held:
+                    if held:
+                        target_dir = base / str(slot)
+                        target_dir.mkdir(parents=True, exist_ok=True)
+                        try:
+                            yield target_dir
+                        finally:
+                            with self._slot_available:
+                                self._slot_available.notify_all()
+                        return
+            with self._slot_available:
+                # Slots held by other processes are not notified about; poll for them
+                self._slot_available.wait(timeout=1.0)
+
+
+class TemplateCache:
+    """
+    Prebuilt trees of a repository revision, with the dependencies already compiled.
+
+    The first checkout of a key builds a template: a copy of the source tree in
+    which `prepare` has run (e.g. cargo build --all-targets). Every checkout
+    then copies the template, target/ included, so only the crates whose
+    sources change afterwards are recompiled.
+
+    Large target directories are copied with reflinks where the filesystem
+    supports them (copy-on-write, so nothing is shared after a write). With
+    link="hardlink", dependency artifacts are hard-linked instead; the output
+    of the workspace's own crates is still copied, since cargo and rustc
+    rewrite those files in place when the crates rebuild.
+    """
+
+    LINK_MODES = ("auto", "reflink", "hardlink", "copy")
+
+    def __init__(self, root: Path, link: str = "auto"):
+        if link not in self.LINK_MODES:
+            raise ValueError(f"Unknown link mode: {link}. Valid modes: {list(self.LINK_MODES)}")
+        self.root = Path(root)
+        self.link = link
+        self._locks = KeyedLock(self.root)
+
+    def template_path(self, key: str) -> Path:
+        """Where the template for key is (or will be) kept."""
+        return _key_dir(self.root, key) / "tree"
+
+    def checkout(
+        self,
+        key: str,
+        source: Path,
+        destination: Path,
+        prepare: Callable[[Path], object],
+        workspace_crates: Optional[List[str]] = None,
+    ) -> bool:
+        """
+        Copy the template for key to destination, building it from source first if needed.
+
+        Args:
+            key: Identifies the repository revision, e.g. "<url>@<commit>"
+            source: A pristine tree of that revision to build the template from
+            destination: Where to put the copy; must not exist yet
+            prepare: Called with the template directory to compile it
+            workspace_crates: Names of the workspace's own crates, whose build
+                output is never hard-linked (default: the package in Cargo.toml)
+
+        Returns:
+            Whether an existing template was used
+        """
+        template = self.template_path(key)
+        existed = True
+        with self._locks.hold(template.parent.name):
+            if not (template.parent / "ready").exists():
+                existed = False
+                if template.exists():
+                    # Left behind by an interrupted prepare
+                    shutil.rmtree(template)
+                # VCS metadata (e.g. a worktree's .git file) would be stale in every copy
+                shutil.copytree(source, template, symlinks=True, ignore=shutil.ignore_patterns(".git"))
+                prepare(template)
+                (template.parent / "ready").touch()
+
+        crates = workspace_crates if workspace_crates is not None else _package_names(template)
+        copy_tree(template, destination, self.link, mutable=crates)
+        return existed
+
+
+def copy_tree(source: Path, destination: Path, link: str = "auto", mutable: Optional[List[str]] = None):
+    """
+    Copy a crate tree, sharing the data of its target directory where possible.
+
+    Args:
+        source: Tree to copy
+        destination: Where to copy it to; must not exist yet
+        link: "reflink" (copy-on-write, or fail), "hardlink", "copy", or "auto"
+            for reflinks where supported and plain copies otherwise
+        mutable: Crate names whose build output must not be hard-linked
+    """
+    if link in ("auto", "reflink") and _cp_reflink(source, destination, always=link == "reflink"):
+        return
+    if link == "reflink":
+        raise OSError(f"Reflinks are not supported for {source}")
+
+    target = source / "target"
+    ignore = shutil.ignore_patterns("target") if link == "hardlink" else None
+    shutil.copytree(source, destination, symlinks=True, ignore=ignore)
+    if link == "hardlink" and target.is_dir():
+        prefixes = _output_prefixes(mutable or [])
+
+        def link_or_copy(src: str, dst: str) -> str:
+            name = os.path.basename(src)
+            # Lock files (.cargo-lock) must not be shared, or copies would block each other
+            shared = not name.startswith('.') and os.path.dirname(src) != str(target)
+            if not shared or any(name.startswith(prefix) for prefix in prefixes):
+                return shutil.copy2(src, dst)
+            try:
+                os.link(src, dst)
+            except OSError:
+                return shutil.copy2(src, dst)
+            return dst
+
+        def ignore_mutable(directory: str, names: List[str]) -> List[str]:
+            # Directories of mutable crates (.fingerprint/<crate>-<hash>, incremental/...) are copied separately
+            return [name for name in names if any(name.startswith(p) for p in prefixes)
+                    and os.path.isdir(os.path.join(directory, name))]
+
+        shutil.copytree(target, destination / "target", symlinks=True,
+                        copy_function=link_or_copy, ignore=ignore_mutable)
+        for directory in target.rglob("*"):
+            if directory.is_dir() and any(directory.name.startswith(p) for p in prefixes):
+                copy = destination / "target" / directory.relative_to(target)
+                if not copy.exists():
+                    shutil.copytree(directory, copy, symlinks=True)
+
+
+def _cp_reflink(source: Path, destination: Path, always: bool) -> bool:
+    """Copy with GNU cp's reflink support; returns False if cp isn't usable."""
+    if shutil.which("cp") is None:
+        return False
+    mode = "always" if always else "auto"
+    result = subprocess.run(
+        ["cp", "-a", f"--reflink={mode}", str(source), str(destination)],
+        capture_output=True,
+    )
+    if result.returncode != 0:
+        # BSD cp has no --reflink; don't leave a partial copy behind
+        shutil.rmtree(destination, ignore_errors=True)
+        return False
+    return True
+
+
+def _output_prefixes(crates: List[str]) -> List[str]:
+    """File name prefixes of a crate's build output: fingerprints, deps, incremental and build dirs."""
+    prefixes = []
+    for crate in crates:
+        for name in {crate, crate.replace('-', '_')}:
+            prefixes.extend([f"{name}-", f"lib{name}-", f"lib{name}.", f"{name}."])
+    return prefixes
+
+
+def _package_names(root: Path) -> List[str]:
+    """Names of the packages declared directly in a tree's Cargo.toml files."""
+    names = []
+    for manifest in root.rglob("Cargo.toml"):
+        if "target" in manifest.relative_to(root).parts:
+            continue
+        match = re.search(r'^\[package\][^\[]*?^name\s*=\s*"([^"]+)"', manifest.read_text(encoding='utf-8'),
+                          re.MULTILINE | re.DOTALL)
+        if match:
+            names.append(match.group(1))
+    return names
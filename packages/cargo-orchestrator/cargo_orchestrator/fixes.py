@@ -0,0 +1,105 @@
+from collections import defaultdict
+from dataclasses import dataclass, field
+from pathlib import Path
+from typing import Dict, Iterable, List, Tuple
+
+from .parser import Applicability, BuildMessage, Suggestion
+
+
+@dataclass
+class FixResult:
+    """Outcome of applying suggested fixes to a source tree."""
+    applied: List[Suggestion] = field(default_factory=list)
+    skipped: List[Suggestion] = field(default_factory=list)
+    files_changed: List[Path] = field(default_factory=list)
+
+
+def collect_suggestions(
+    messages: Iterable[BuildMessage],
+    applicabilities: Iterable[Applicability] = (Applicability.MACHINE_APPLICABLE,),
+) -> List[Suggestion]:
+    """
+    Collect the suggestions of the given applicability from parsed messages.
+    
+    Args:
+        messages: Messages parsed from JSON cargo output (human output has no suggestions)
+        applicabilities: Which applicability levels to include
+    
+    Returns:
+        List of Suggestion objects, in message order
+    """
+    wanted = set(applicabilities)
+    return [
+        suggestion
+        for message in messages
+        for suggestion in message.suggestions()
+        if suggestion.applicability in wanted
+    ]
+
+
+def apply_suggestions(
+    messages: Iterable[BuildMessage],
+    root_dir: Path,
+    applicabilities: Iterable[Applicability] = (Applicability.MACHINE_APPLICABLE,),
+) -> FixResult:
+    """
+    Apply suggested fixes from compiler messages to the source files.
+    
+    Only MachineApplicable suggestions are applied by default. Like rustfix,
+    a suggestion is applied as a whole or not at all; suggestions that overlap
+    one applied earlier (or repeat it, as happens when a crate is checked for
+    several targets) are skipped.
+    
+    Args:
+        messages: Messages parsed from JSON cargo output
+        root_dir: Directory the span file names are relative to (the workspace root)
+        applicabilities: Which applicability levels to apply
+    
+    Returns:
+        FixResult listing the applied and skipped suggestions and the changed files
+    """
+    result = FixResult()
+    edits: Dict[Path, List[Tuple[int, int, str]]] = defaultdict(list)
+    seen = set()
+    
+    for suggestion in collect_suggestions(messages, applicabilities):
+        key = tuple(
+            (span.file_name, span.byte_start, span.byte_end, span.suggested_replacement)
+            for span in suggestion.replacements
+        )
+        if key in seen:
+            continue
+        seen.add(key)
+        
+        new_edits = [
+            (root_dir / span.file_name, span.byte_start, span.byte_end, span.suggested_replacement)
+            for span in suggestion.replacements
+        ]
+        if any(_overlaps(edits[path], start, end) for path, start, end, _ in new_edits):
+            result.skipped.append(suggestion)
+            continue
+        
+        for path, start, end, replacement in new_edits:
+            edits[path].append((start, end, replacement))
+        result.applied.append(suggestion)
+    
+    for path, file_edits in edits.items():
+        # Byte offsets refer to the UTF-8 encoded source; apply back to front
+        source = path.read_bytes()
+        for start, end, replacement in sorted(file_edits, reverse=True):
+            source = source[:start] + replacement.encode('utf-8') + source[end:]
+        path.write_bytes(source)
+        result.files_changed.append(path)
+    
+    return result
+
+
+def _overlaps(edits: List[Tuple[int, int, str]], start: int, end: int) -> bool:
+    """Check whether a replacement of [start, end) collides with existing edits."""
+    for other_start, other_end, _ in edits:
+        if start < other_end and other_start < end:
+            return True
+        # Two insertions at the same point would have an undefined order
+        if start == end == other_start == other_end:
+            return True
+    return False
@@ -0,0 +1,170 @@
+import json
+import re
+import subprocess
+from dataclasses import dataclass
+from enum import Enum
+from pathlib import Path
+from typing import Dict, Optional
+
+
+# Groups every clippy lint belongs to exactly one of; "all" and the rustc
+# groups are umbrella groups and are not used for classification.
+CLIPPY_GROUPS = (
+    "correctness",
+    "suspicious",
+    "style",
+    "complexity",
+    "perf",
+    "pedantic",
+    "restriction",
+    "nursery",
+    "cargo",
+)
+
+BUNDLED_LINTS_PATH = Path(__file__).parent / "clippy_lints.json"
+
+
+class LintLevel(Enum):
+    ALLOW = "allow"
+    WARN = "warn"
+    DENY = "deny"
+    FORBID = "forbid"
+
+
+@dataclass
+class LintInfo:
+    """Classification of a single rustc or clippy lint."""
+    name: str
+    group: Optional[str] = None
+    default_level: Optional[LintLevel] = None
+    
+    @property
+    def is_clippy(self) -> bool:
+        return self.name.startswith("clippy::")
+
+
+class LintRegistry:
+    """
+    Lookup table from lint codes (e.g. "clippy::needless_return") to their
+    clippy group and default level.
+    
+    Clippy's JSON diagnostics only carry the lint code, so the group has to
+    come from the toolchain's own lint listing (clippy-driver -W help). A
+    snapshot of that listing ships with the package; from_toolchain() reads
+    the installed one instead.
+    """
+    
+    def __init__(self, lints: Optional[Dict[str, LintInfo]] = None, clippy_version: Optional[str] = None):
+        self.lints = lints or {}
+        self.clippy_version = clippy_version
+    
+    def lookup(self, code: Optional[str]) -> Optional[LintInfo]:
+        """
+        Resolve a diagnostic code to its lint.
+        
+        Args:
+            code: The diagnostic code, e.g. "clippy::needless_return" or "dead_code"
+        
+        Returns:
+            The LintInfo, or None for error codes such as "E0308" and unknown lints
+        """
+        if not code:
+            return None
+        return self.lints.get(normalize_lint_name(code))
+    
+    @classmethod
+    def bundled(cls) -> 'LintRegistry':
+        """Load the snapshot of clippy lints shipped with the package."""
+        data = json.loads(BUNDLED_LINTS_PATH.read_text(encoding='utf-8'))
+        lints = {
+            name: LintInfo(
+                name=name,
+                group=info.get("group"),
+                default_level=LintLevel(info["default_level"]) if info.get("default_level") else None,
+            )
+            for name, info in data.get("lints", {}).items()
+        }
+        return cls(lints, clippy_version=data.get("clippy_version"))
+    
+    @classmethod
+    def from_help_output(cls, output: str) -> 'LintRegistry':
+        """
+        Build a registry from the output of `clippy-driver -W help`.
+        
+        Args:
+            output: The lint listing printed by clippy-driver (or rustc)
+        
+        Returns:
+            A LintRegistry with every listed lint and its group, if any
+        """
+        lints: Dict[str, LintInfo] = {}
+        
+        # Example: "    clippy::needless-return  warn     using a return statement like `return expr;`..."
+        lint_pattern = re.compile(r'^\s*([a-z0-9_:-]+)\s+(allow|warn|deny|forbid)\s', re.MULTILINE)
+        
+        # Example: "    clippy::style  clippy::assertions-on-constants, clippy::mixed-attributes-style, ..."
+        group_pattern = re.compile(r'^\s*clippy::([a-z]+)\s+(clippy::.+)$', re.MULTILINE)
+        
+        for match in lint_pattern.finditer(output):
+            name = normalize_lint_name(match.group(1))
+            lints[name] = LintInfo(name=name, default_level=LintLevel(match.group(2)))
+        
+        for match in group_pattern.finditer(output):
+            group = match.group(1)
+            if group not in CLIPPY_GROUPS:
+                continue
+            for member in match.group(2).split(','):
+                name = normalize_lint_name(member.strip())
+                if name in lints:
+                    lints[name].group = group
+        
+        return cls(lints)
+    
+    @classmethod
+    def from_toolchain(cls, clippy_driver: str = "clippy-driver") -> 'LintRegistry':
+        """
+        Build a registry from the installed clippy.
+        
+        Args:
+            clippy_driver: Path to the clippy-driver executable
+        
+        Returns:
+            A LintRegistry matching the installed clippy version
+        """
+        help_output = subprocess.run(
+            [clippy_driver, "-W", "help"],
+            capture_output=True,
+            text=True,
+            check=True,
+        ).stdout
+        version = subprocess.run(
+            [clippy_driver, "--version"],
+            capture_output=True,
+            text=True,
+        ).stdout.split()
+        
+        registry = cls.from_help_output(help_output)
+        registry.clippy_version = version[1] if len(version) > 1 else None
+        return registry
+    
+    def to_json(self) -> str:
+        """Serialize the clippy lints in the format of the bundled snapshot, one lint per line."""
+        entries = [
+            f'  {json.dumps(name)}: ' + json.dumps({
+                "group": info.group,
+                "default_level": info.default_level.value if info.default_level else None,
+            })
+            for name, info in sorted(self.lints.items())
+            if info.is_clippy
+        ]
+        return (
+            '{\n'
+            f' "clippy_version": {json.dumps(self.clippy_version)},\n'
+            ' "lints": {\n' + ',\n'.join(entries) + '\n }\n'
+            '}'
+        )
+
+
+def normalize_lint_name(name: str) -> str:
+    """Normalize a lint name as printed by -W help ("clippy::needless-return") to its code form."""
+    return name.strip().replace('-', '_')
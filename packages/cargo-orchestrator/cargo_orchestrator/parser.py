@@ -1,8 +1,12 @@
+import hashlib
 import json
 import re
 from typing import List, Dict, Any, Optional
 from dataclasses import dataclass
 from enum import Enum
+from functools import lru_cache
+
+from .lints import LintInfo, LintLevel, LintRegistry
 
 
 class MessageLevel(Enum):
@@ -13,6 +17,14 @@ class MessageLevel(Enum):
     INFO = "info"
 
 
+class Applicability(Enum):
+    """How confident the compiler is that a suggested replacement is correct."""
+    MACHINE_APPLICABLE = "MachineApplicable"
+    MAYBE_INCORRECT = "MaybeIncorrect"
+    HAS_PLACEHOLDERS = "HasPlaceholders"
+    UNSPECIFIED = "Unspecified"
+
+
 @dataclass
 class CodeSpan:
     """Represents a span of code in a file."""
@@ -22,6 +34,23 @@ class CodeSpan:
     column_start: int
     column_end: int
     text: Optional[str] = None
+    byte_start: Optional[int] = None
+    byte_end: Optional[int] = None
+    suggested_replacement: Optional[str] = None
+    suggestion_applicability: Optional[Applicability] = None
+    is_primary: bool = True
+    
+    @property
+    def has_suggestion(self) -> bool:
+        return self.suggested_replacement is not None and self.byte_start is not None
+
+
+@dataclass
+class Suggestion:
+    """A suggested fix: replacements that must be applied together."""
+    message: str
+    applicability: Applicability
+    replacements: List[CodeSpan]
 
 
 @dataclass
@@ -33,17 +62,175 @@ class BuildMessage:
     spans: List[CodeSpan] = None
     children: List['BuildMessage'] = None
     rendered: Optional[str] = None
+    lint: Optional[LintInfo] = None
     
     def __post_init__(self):
         if self.spans is None:
             self.spans = []
         if self.children is None:
             self.children = []
+    
+    @property
+    def is_clippy_lint(self) -> bool:
+        return self.lint is not None and self.lint.is_clippy
+    
+    @property
+    def lint_group(self) -> Optional[str]:
+        """The clippy group of the lint, e.g. "correctness" or "style"."""
+        return self.lint.group if self.lint else None
+    
+    @property
+    def primary_span(self) -> Optional[CodeSpan]:
+        """The span the message points at, or None for messages without a location."""
+        for span in self.spans:
+            if span.is_primary:
+                return span
+        return self.spans[0] if self.spans else None
+    
+    def fingerprint(self, item: Optional[str] = None) -> str:
+        """
+        Identify the diagnostic independently of where exactly it is reported.
+        
+        Line and column numbers shift whenever code above a diagnostic changes,
+        so they are left out: the fingerprint covers the level, the code, the
+        normalized message, the file and (if given) the enclosing item.
+        
+        Args:
+            item: Path of the item the primary span lies in, e.g. "Stack::pop"
+        
+        Returns:
+            A hex digest that is equal for the same diagnostic in two builds
+        """
+        span = self.primary_span
+        parts = [
+            self.level.value,
+            self.code or "",
+            normalize_message(self.message),
+            span.file_name.replace('\\', '/') if span else "",
+            item or "",
+        ]
+        return hashlib.sha1('\x1f'.join(parts).encode('utf-8')).hexdigest()[:16]
+    
+    def suggestions(self) -> List[Suggestion]:
+        """
+        Collect the suggested fixes attached to this message and its children.
+        
+        Each help child with suggested replacements becomes one Suggestion, since
+        its spans (e.g. both parentheses of "remove these parentheses") only make
+        sense applied together.
+        """
+        suggestions = []
+        for message in [self] + self.children:
+            replacements = [span for span in message.spans if span.has_suggestion]
+            if not replacements:
+                continue
+            applicabilities = {span.suggestion_applicability for span in replacements}
+            # A mixed suggestion is only as applicable as its least certain part
+            applicability = applicabilities.pop() if len(applicabilities) == 1 else Applicability.UNSPECIFIED
+            suggestions.append(Suggestion(
+                message=message.message,
+                applicability=applicability or Applicability.UNSPECIFIED,
+                replacements=replacements,
+            ))
+        return suggestions
+
+
+@dataclass
+class Artifact:
+    """A compiled target reported by a compiler-artifact message."""
+    package_id: str
+    target_name: str
+    kinds: List[str]
+    crate_types: List[str]
+    filenames: List[str]
+    executable: Optional[str] = None
+    fresh: bool = False
+    test: bool = False
+    
+    @property
+    def is_build_script(self) -> bool:
+        return "custom-build" in self.kinds
+
+
+@dataclass
+class BuildScriptOutput:
+    """Directives emitted by a build script, reported by a build-script-executed message."""
+    package_id: str
+    out_dir: Optional[str] = None
+    linked_libs: List[str] = None
+    linked_paths: List[str] = None
+    cfgs: List[str] = None
+    env: Dict[str, str] = None
+    
+    def __post_init__(self):
+        if self.linked_libs is None:
+            self.linked_libs = []
+        if self.linked_paths is None:
+            self.linked_paths = []
+        if self.cfgs is None:
+            self.cfgs = []
+        if self.env is None:
+            self.env = {}
+
+
+class TestOutcome(Enum):
+    PASSED = "passed"
+    FAILED = "failed"
+    IGNORED = "ignored"
+
+
+@dataclass
+class TestResult:
+    """Represents the outcome of a single test reported by the libtest harness."""
+    name: str
+    outcome: TestOutcome
+    duration: Optional[float] = None
+    stdout: Optional[str] = None
+    suite: Optional[str] = None  # test target, e.g. "unittests src/lib.rs" or "tests/api.rs"
+    
+    @property
+    def passed(self) -> bool:
+        return self.outcome == TestOutcome.PASSED
+    
+    @property
+    def doc_test(self) -> bool:
+        """Whether the test is a documentation example rather than a #[test] function."""
+        return DOC_TEST_NAME_PATTERN.match(self.name) is not None
+
+
+# Example: "src/lib.rs - Stack::pop (line 12)"
+DOC_TEST_NAME_PATTERN = re.compile(r'^\S+ - .*\(line \d+\)$')
+
+# Example: "`#[warn(clippy::needless_return)]` on by default"
+LINT_ATTRIBUTE_PATTERN = re.compile(r'`#\[(allow|warn|deny|forbid)\(([a-z0-9_:]+)\)\]`(.*)$')
+
+# Example: "E0425"
+ERROR_CODE_PATTERN = re.compile(r'^E\d{4}$')
+
+
+@lru_cache(maxsize=None)
+def _bundled_registry() -> LintRegistry:
+    return LintRegistry.bundled()
+
+
+def normalize_message(message: str) -> str:
+    """Strip the parts of a diagnostic message that change between otherwise equal builds."""
+    # Example: "function defined here at src/lib.rs:12:5" -> "function defined here at src/lib.rs"
+    message = re.sub(r'(\.rs):\d+(:\d+)?', r'\1', message)
+    return ' '.join(message.split())
 
 
 class CargoOutputParser:
     """Parser for cargo build output in both JSON and human-readable formats."""
     
+    def __init__(self, lint_registry: Optional[LintRegistry] = None):
+        """
+        Args:
+            lint_registry: Lint table used to classify clippy lints
+                (default: the snapshot bundled with the package)
+        """
+        self.lint_registry = lint_registry or _bundled_registry()
+    
     def parse_json_output(self, output: str) -> List[BuildMessage]:
         """
         Parse JSON-formatted cargo output.
@@ -78,6 +265,89 @@ class CargoOutputParser:
         
         return messages
     
+    def parse_artifacts(self, output: str) -> List[Artifact]:
+        """
+        Parse the compiler-artifact messages from JSON-formatted cargo output.
+        
+        Args:
+            output: The stdout from cargo with --message-format json
+            
+        Returns:
+            List of Artifact objects, in build order
+        """
+        artifacts = []
+        
+        for data in self._json_records(output, 'compiler-artifact'):
+            target = data.get('target', {})
+            artifacts.append(Artifact(
+                package_id=data.get('package_id', ''),
+                target_name=target.get('name', ''),
+                kinds=target.get('kind', []),
+                crate_types=target.get('crate_types', []),
+                filenames=data.get('filenames', []),
+                executable=data.get('executable'),
+                fresh=data.get('fresh', False),
+                test=data.get('profile', {}).get('test', False),
+            ))
+        
+        return artifacts
+    
+    def parse_build_scripts(self, output: str) -> List[BuildScriptOutput]:
+        """
+        Parse the build-script-executed messages from JSON-formatted cargo output.
+        
+        Args:
+            output: The stdout from cargo with --message-format json
+            
+        Returns:
+            List of BuildScriptOutput objects, one per executed build script
+        """
+        return [
+            BuildScriptOutput(
+                package_id=data.get('package_id', ''),
+                out_dir=data.get('out_dir'),
+                linked_libs=data.get('linked_libs', []),
+                linked_paths=data.get('linked_paths', []),
+                cfgs=data.get('cfgs', []),
+                env={name: value for name, value in data.get('env', [])},
+            )
+            for data in self._json_records(output, 'build-script-executed')
+        ]
+    
+    def parse_build_finished(self, output: str) -> Optional[bool]:
+        """
+        Parse the build-finished message from JSON-formatted cargo output.
+        
+        Args:
+            output: The stdout from cargo with --message-format json
+            
+        Returns:
+            The reported success flag, or None if cargo stopped before reporting it
+        """
+        finished = None
+        for data in self._json_records(output, 'build-finished'):
+            # cargo test reports once for the build; keep the last one
+            finished = data.get('success')
+        return finished
+    
+    def _json_records(self, output: str, reason: str) -> List[Dict[str, Any]]:
+        """Decode the JSON lines of cargo output with the given reason."""
+        records = []
+        
+        for line in output.strip().split('\n'):
+            if not line.startswith('{'):
+                continue
+            
+            try:
+                data = json.loads(line)
+            except json.JSONDecodeError:
+                continue
+            
+            if isinstance(data, dict) and data.get('reason') == reason:
+                records.append(data)
+        
+        return records
+    
     def _parse_json_message(self, data: Dict[str, Any]) -> Optional[BuildMessage]:
         """Parse a single JSON message object."""
         level_str = data.get('level', '').lower()
@@ -109,7 +379,7 @@ class CargoOutputParser:
             if child:
                 children.append(child)
         
-        return BuildMessage(
+        message = BuildMessage(
             level=level,
             message=data.get('message', ''),
             code=data.get('code', {}).get('code') if data.get('code') else None,
@@ -117,6 +387,29 @@ class CargoOutputParser:
             children=children,
             rendered=data.get('rendered'),
         )
+        message.lint = self._classify_lint(message.code, [child.message for child in children])
+        return message
+    
+    def _classify_lint(self, code: Optional[str], notes: List[str]) -> Optional[LintInfo]:
+        """
+        Resolve a diagnostic code to its lint name, clippy group and default level.
+        
+        Lints missing from the registry (e.g. from a newer clippy) still get their
+        name, and their default level if a note says they are "on by default".
+        """
+        if not code or ERROR_CODE_PATTERN.match(code):
+            return None
+        
+        lint = self.lint_registry.lookup(code)
+        if lint:
+            return lint
+        
+        default_level = None
+        for note in notes:
+            match = LINT_ATTRIBUTE_PATTERN.search(note)
+            if match and match.group(2) == code and 'on by default' in match.group(3):
+                default_level = LintLevel(match.group(1))
+        return LintInfo(name=code, default_level=default_level)
     
     def _parse_span(self, span_data: Dict[str, Any]) -> Optional[CodeSpan]:
         """Parse a span object from JSON."""
@@ -124,6 +417,12 @@ class CargoOutputParser:
         if not file_name:
             return None
         
+        applicability = span_data.get('suggestion_applicability')
+        if applicability:
+            # Newer compilers may add applicability levels we don't know yet
+            known = {a.value: a for a in Applicability}
+            applicability = known.get(applicability, Applicability.UNSPECIFIED)
+        
         return CodeSpan(
             file_name=file_name,
             line_start=span_data.get('line_start', 0),
@@ -131,6 +430,11 @@ class CargoOutputParser:
             column_start=span_data.get('column_start', 0),
             column_end=span_data.get('column_end', 0),
             text=span_data.get('text', [{}])[0].get('text') if span_data.get('text') else None,
+            byte_start=span_data.get('byte_start'),
+            byte_end=span_data.get('byte_end'),
+            suggested_replacement=span_data.get('suggested_replacement'),
+            suggestion_applicability=applicability,
+            is_primary=span_data.get('is_primary', True),
         )
     
     def parse_human_output(self, output: str) -> List[BuildMessage]:
@@ -161,9 +465,13 @@ class CargoOutputParser:
             re.MULTILINE
         )
         
+        # Example: "   = note: `#[warn(clippy::needless_return)]` on by default"
+        note_pattern = re.compile(r'^\s*= note: (.+)$')
+        
         # Split output into sections by error/warning messages
         current_message = None
         current_text = []
+        current_notes = []
         
         for line in output.split('\n'):
             # Check if this is a new error/warning
@@ -171,7 +479,7 @@ class CargoOutputParser:
             if match:
                 # Save previous message if exists
                 if current_message:
-                    current_message.rendered = '\n'.join(current_text)
+                    self._finish_human_message(current_message, current_text, current_notes)
                     messages.append(current_message)
                 
                 # Start new message
@@ -192,10 +500,15 @@ class CargoOutputParser:
                     code=code,
                 )
                 current_text = [line]
+                current_notes = []
             
             elif current_message:
                 current_text.append(line)
                 
+                note_match = note_pattern.match(line)
+                if note_match:
+                    current_notes.append(note_match.group(1))
+                
                 # Check for location information
                 loc_match = location_pattern.match(line)
                 if loc_match:
@@ -214,7 +527,171 @@ class CargoOutputParser:
         
         # Don't forget the last message
         if current_message:
-            current_message.rendered = '\n'.join(current_text)
+            self._finish_human_message(current_message, current_text, current_notes)
             messages.append(current_message)
         
-        return messages
\ No newline at end of file
+        return messages
+    
+    def _finish_human_message(self, message: BuildMessage, text: List[str], notes: List[str]):
+        """Attach the rendered text and lint classification to a human-format message."""
+        message.rendered = '\n'.join(text)
+        
+        # Lints have no "[code]" after the level; take the name from the lint level note
+        if message.code is None and message.level in (MessageLevel.ERROR, MessageLevel.WARNING):
+            for note in notes:
+                match = LINT_ATTRIBUTE_PATTERN.search(note)
+                if match:
+                    message.code = match.group(2)
+                    break
+        
+        message.lint = self._classify_lint(message.code, notes)
+    
+    def parse_test_suites(self, output: str) -> List[str]:
+        """
+        Parse the test targets cargo test ran, in the order it ran them.
+        
+        Args:
+            output: The stderr from cargo test
+            
+        Returns:
+            List of suite names such as "unittests src/lib.rs", "tests/api.rs"
+            or "Doc-tests my_crate"
+        """
+        suites = []
+        
+        # Example: "     Running unittests src/lib.rs (target/debug/deps/my_crate-1a2b3c)"
+        running_pattern = re.compile(r'^\s+Running (.+?)(?: \(.*\))?$')
+        
+        # Example: "   Doc-tests my_crate"
+        doc_tests_pattern = re.compile(r'^\s+(Doc-tests \S+)$')
+        
+        for line in output.split('\n'):
+            match = running_pattern.match(line) or doc_tests_pattern.match(line)
+            if match:
+                suites.append(match.group(1))
+        
+        return suites
+    
+    def parse_test_output(self, output: str, suites: Optional[List[str]] = None) -> List[TestResult]:
+        """
+        Parse libtest output from cargo test.
+        
+        Both the default human format ("test foo ... ok") and the unstable
+        JSON format (-Z unstable-options --format json) are recognised, so
+        the stdout of cargo test can be passed in as-is even when it is
+        interleaved with cargo's own JSON messages.
+        
+        Args:
+            output: The stdout from cargo test
+            suites: The test targets from parse_test_suites(); each test harness
+                run on stdout is matched to the next one, setting TestResult.suite
+            
+        Returns:
+            List of TestResult objects, one per test that finished
+        """
+        results = []
+        failure_output: Dict[str, List[str]] = {}
+        suite_index = -1
+        
+        # Example: "test tests::test_fibonacci ... FAILED"
+        result_pattern = re.compile(r'^test (.+?) \.\.\. (ok|FAILED|ignored)(?:, .*)?$')
+        
+        # Example: "---- tests::test_fibonacci stdout ----"
+        failure_header_pattern = re.compile(r'^---- (.+?) stdout ----$')
+        
+        # Example: "running 3 tests", printed once per test harness
+        harness_pattern = re.compile(r'^running \d+ tests?$')
+        
+        outcome_map = {
+            'ok': TestOutcome.PASSED,
+            'FAILED': TestOutcome.FAILED,
+            'ignored': TestOutcome.IGNORED,
+        }
+        
+        json_outcome_map = {
+            'ok': TestOutcome.PASSED,
+            'failed': TestOutcome.FAILED,
+            'timeout': TestOutcome.FAILED,
+            'ignored': TestOutcome.IGNORED,
+        }
+        
+        current_failure = None
+        
+        def suite() -> Optional[str]:
+            return suites[suite_index] if suites and 0 <= suite_index < len(suites) else None
+        
+        for line in output.split('\n'):
+            if line.startswith('{'):
+                try:
+                    data = json.loads(line)
+                except json.JSONDecodeError:
+                    data = None
+                
+                if isinstance(data, dict):
+                    if data.get('type') == 'suite' and data.get('event') == 'started':
+                        suite_index += 1
+                    elif data.get('type') == 'test' and data.get('event') in json_outcome_map:
+                        results.append(TestResult(
+                            name=data.get('name', ''),
+                            outcome=json_outcome_map[data['event']],
+                            duration=data.get('exec_time'),
+                            stdout=data.get('stdout'),
+                            suite=suite(),
+                        ))
+                    # Cargo messages and suite events carry no per-test result
+                    continue
+            
+            header_match = failure_header_pattern.match(line)
+            if header_match:
+                current_failure = header_match.group(1)
+                failure_output[current_failure] = []
+                continue
+            
+            if current_failure is not None:
+                if line == 'failures:':
+                    current_failure = None
+                else:
+                    failure_output[current_failure].append(line)
+                continue
+            
+            if harness_pattern.match(line):
+                suite_index += 1
+                continue
+            
+            match = result_pattern.match(line)
+            if match:
+                results.append(TestResult(
+                    name=match.group(1),
+                    outcome=outcome_map[match.group(2)],
+                    suite=suite(),
+                ))
+        
+        # Attach captured output from the "failures:" section
+        for result in results:
+            if result.stdout is None and result.name in failure_output:
+                result.stdout = '\n'.join(failure_output[result.name]).strip()
+        
+        return results
+    
+    def parse_fix_output(self, output: str) -> Dict[str, int]:
+        """
+        Parse the fixes reported by cargo fix or cargo clippy --fix.
+        
+        Args:
+            output: The stderr from cargo fix
+            
+        Returns:
+            Dict mapping each fixed file to the number of fixes applied to it
+        """
+        fixes: Dict[str, int] = {}
+        
+        # Example: "       Fixed src/main.rs (3 fixes)"
+        fixed_pattern = re.compile(r'^\s*Fixed (.+) \((\d+) fix(?:es)?\)$')
+        
+        for line in output.split('\n'):
+            match = fixed_pattern.match(line)
+            if match:
+                # A file can be reported once per target it belongs to
+                fixes[match.group(1)] = fixes.get(match.group(1), 0) + int(match.group(2))
+        
+        return fixes
\ No newline at end of file
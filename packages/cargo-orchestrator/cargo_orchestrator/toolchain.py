@@ -0,0 +1,110 @@
+import os
+import subprocess
+import threading
+from dataclasses import dataclass, field, asdict
+from pathlib import Path
+from typing import Any, Dict, List, Optional, Tuple
+
+# Environment variables that change what the toolchain produces
+TOOLCHAIN_ENV_VARS = (
+    "RUSTFLAGS",
+    "CARGO_ENCODED_RUSTFLAGS",
+    "CARGO_BUILD_RUSTFLAGS",
+    "RUSTDOCFLAGS",
+    "RUSTUP_TOOLCHAIN",
+    "RUSTC_WRAPPER",
+)
+
+TOOLCHAIN_FILES = ("rust-toolchain.toml", "rust-toolchain")
+
+
+@dataclass
+class ToolchainInfo:
+    """
+    Versions of the Rust toolchain a build ran with.
+
+    Diagnostics differ between compiler and clippy versions, so results are
+    only comparable when these match.
+    """
+    rustc: Optional[str] = None  # e.g. "rustc 1.83.0 (90b35a623 2024-11-26)"
+    cargo: Optional[str] = None
+    clippy: Optional[str] = None
+    host: Optional[str] = None  # host triple, e.g. "x86_64-unknown-linux-gnu"
+    release: Optional[str] = None
+    commit_hash: Optional[str] = None
+    llvm_version: Optional[str] = None
+    env: Dict[str, str] = field(default_factory=dict)  # the TOOLCHAIN_ENV_VARS that were set
+
+    def to_dict(self) -> Dict[str, Any]:
+        return asdict(self)
+
+
+_detected: Dict[Tuple, ToolchainInfo] = {}
+_detected_lock = threading.Lock()
+
+
+def toolchain_info(root_dir: Optional[Path] = None, use_nightly: bool = False) -> ToolchainInfo:
+    """
+    Detect the toolchain cargo runs with in root_dir, once per session.
+
+    rustup picks the toolchain from rust-toolchain files in root_dir or its
+    parents and from the environment, so detections are cached per toolchain
+    file and environment rather than globally.
+
+    Args:
+        root_dir: Directory cargo runs in. Defaults to current directory.
+        use_nightly: Whether cargo runs as cargo +nightly.
+
+    Returns:
+        ToolchainInfo; versions of tools that aren't installed are None.
+    """
+    root = Path(root_dir or Path.cwd()).resolve()
+    env = {name: os.environ[name] for name in TOOLCHAIN_ENV_VARS if name in os.environ}
+    key = (use_nightly, _toolchain_file(root), tuple(sorted(env.items())))
+
+    with _detected_lock:
+        if key not in _detected:
+            _detected[key] = _detect(root, use_nightly, env)
+        return _detected[key]
+
+
+def _detect(root: Path, use_nightly: bool, env: Dict[str, str]) -> ToolchainInfo:
+    """Run the version commands of rustc, cargo and clippy."""
+    channel = ["+nightly"] if use_nightly else []
+    info = ToolchainInfo(
+        cargo=_version(["cargo", *channel, "-V"], root),
+        clippy=_version(["clippy-driver", *channel, "-V"], root),
+        env=env,
+    )
+
+    verbose = _version(["rustc", *channel, "-vV"], root)
+    if verbose:
+        lines = verbose.splitlines()
+        info.rustc = lines[0]
+        fields = dict(line.split(": ", 1) for line in lines[1:] if ": " in line)
+        info.host = fields.get("host")
+        info.release = fields.get("release")
+        info.commit_hash = fields.get("commit-hash")
+        info.llvm_version = fields.get("LLVM version")
+    return info
+
+
+def _version(cmd: List[str], cwd: Path) -> Optional[str]:
+    """Output of a version command, or None if the tool isn't available."""
+    try:
+        result = subprocess.run(cmd, cwd=cwd, capture_output=True, text=True, timeout=30)
+    except (OSError, subprocess.TimeoutExpired):
+        return None
+    if result.returncode != 0:
+        return None
+    return result.stdout.strip() or None
+
+
+def _toolchain_file(root: Path) -> Optional[str]:
+    """Contents of the rust-toolchain file that applies in root, if any."""
+    for directory in (root, *root.parents):
+        for name in TOOLCHAIN_FILES:
+            path = directory / name
+            if path.is_file():
+                return path.read_text(encoding="utf-8", errors="replace")
+    return None
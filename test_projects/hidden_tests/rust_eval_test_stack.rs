@@ -0,0 +1,32 @@
+use rust_eval_test::Stack;
+
+#[test]
+fn pop_on_new_stack_is_none() {
+    let mut stack: Stack<i32> = Stack::new();
+    assert!(stack.is_empty());
+    assert_eq!(stack.pop(), None);
+}
+
+#[test]
+fn pops_in_reverse_push_order() {
+    let mut stack = Stack::new();
+    for word in ["a", "b", "c"] {
+        stack.push(word.to_string());
+    }
+    assert_eq!(stack.pop().as_deref(), Some("c"));
+    assert_eq!(stack.pop().as_deref(), Some("b"));
+    stack.push("d".to_string());
+    assert_eq!(stack.pop().as_deref(), Some("d"));
+    assert_eq!(stack.pop().as_deref(), Some("a"));
+    assert!(stack.is_empty());
+}
+
+#[test]
+fn holds_many_items() {
+    let mut stack = Stack::new();
+    for i in 0..10_000 {
+        stack.push(i);
+    }
+    assert_eq!((0..10_000).rev().map(|_| stack.pop().unwrap()).sum::<i32>(), (0..10_000).sum());
+    assert!(stack.is_empty());
+}
@@ -0,0 +1,41 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn wordcount(args: &[&str], input: &str) -> (String, bool) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_wordcount"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("wordcount runs");
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    (String::from_utf8(output.stdout).unwrap(), output.status.success())
+}
+
+#[test]
+fn counts_lines_words_and_bytes() {
+    let (stdout, ok) = wordcount(&[], "one two\nthree\n");
+    assert!(ok);
+    assert_eq!(stdout.split_whitespace().collect::<Vec<_>>(), ["2", "3", "14"]);
+}
+
+#[test]
+fn counts_only_words_with_flag() {
+    let (stdout, ok) = wordcount(&["-w"], "  spaced   out\n\nwords  ");
+    assert!(ok);
+    assert_eq!(stdout.trim(), "3");
+}
+
+#[test]
+fn empty_input() {
+    let (stdout, ok) = wordcount(&[], "");
+    assert!(ok);
+    assert_eq!(stdout.split_whitespace().collect::<Vec<_>>(), ["0", "0", "0"]);
+}
+
+#[test]
+fn rejects_unknown_flag() {
+    let (_, ok) = wordcount(&["--bogus"], "text");
+    assert!(!ok);
+}
@@ -0,0 +1,3 @@
+fn main() {
+    // TODO: Replaced by the program under evaluation
+}
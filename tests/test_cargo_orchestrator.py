@@ -3,11 +3,21 @@
 Test suite for cargo-orchestrator library.
 """
 
+import shutil
+import time
 import pytest
 from pathlib import Path
-from cargo_orchestrator import CargoBuilder, BuildResult
-from cargo_orchestrator.builder import BuildProfile
-from cargo_orchestrator.parser import MessageLevel, CargoOutputParser
+from cargo_orchestrator import CargoBuilder, BuildResult, apply_suggestions, TargetDirPool, TemplateCache
+from cargo_orchestrator.builder import BuildProfile, BuildStatus, ResourceLimits
+from cargo_orchestrator.parser import MessageLevel, CargoOutputParser, Applicability
+from cargo_orchestrator.lints import LintRegistry, LintLevel
+
+
+def copy_project(name: str, tmp_path: Path) -> Path:
+    """Copy a test project so fixes don't modify the original."""
+    destination = tmp_path / name
+    shutil.copytree(Path("test_projects") / name, destination, ignore=shutil.ignore_patterns("target"))
+    return destination
 
 
 class TestCargoBuilder:
@@ -25,6 +35,133 @@ class TestCargoBuilder:
         assert result.return_code == 0
         assert len(result.messages) == 0  # No errors or warnings
     
+    def test_toolchain_provenance(self, monkeypatch):
+        """Test that results record the toolchain, detected once per environment."""
+        monkeypatch.setenv("RUSTFLAGS", "-C debuginfo=0")
+        builder = CargoBuilder(root_dir=Path("test_projects/success_project"))
+        
+        result = builder.build()
+        
+        assert result.toolchain.rustc.startswith("rustc ")
+        assert result.toolchain.cargo.startswith("cargo ")
+        assert result.toolchain.host
+        assert result.toolchain.env == {"RUSTFLAGS": "-C debuginfo=0"}
+        assert CargoBuilder(root_dir=Path("test_projects/warning_project")).toolchain is result.toolchain
+    
+    def test_build_artifacts(self):
+        """Test locating the built binary from compiler-artifact messages."""
+        builder = CargoBuilder(
+            root_dir=Path("test_projects/success_project")
+        )
+        result = builder.build()
+        
+        executable = result.find_executable("success_project")
+        assert result.build_finished is True
+        assert executable is not None and executable.is_file()
+        assert result.executables == [executable]
+    
+    def test_run_binary(self):
+        """Test building and running a binary with captured output."""
+        builder = CargoBuilder(
+            root_dir=Path("test_projects/success_project")
+        )
+        result = builder.run()
+        
+        assert result.success is True
+        assert result.exit_code == 0
+        assert "Sum of numbers: 15\n5! = 120\n" in result.stdout
+        assert result.executable == result.build.find_executable("success_project")
+    
+    def test_manifest_metadata(self, tmp_path):
+        """Test reading a manifest's dependencies, and that a broken manifest raises."""
+        crate = copy_project("success_project", tmp_path)
+        manifest = crate / "Cargo.toml"
+        manifest.write_text(manifest.read_text() + 'rx = { package = "regex", version = "1" }\n')
+        builder = CargoBuilder(root_dir=crate)
+        
+        package = builder.metadata()["packages"][0]
+        
+        assert package["name"] == "success_project"
+        assert [(d["name"], d["rename"], d["req"]) for d in package["dependencies"]] == [("regex", "rx", "^1")]
+        manifest.write_text(manifest.read_text() + "broken =\n")
+        with pytest.raises(RuntimeError, match="Cargo.toml"):
+            builder.metadata()
+    
+    def test_run_with_input_and_timeout(self, tmp_path):
+        """Test passing args and stdin, and killing a program that runs too long."""
+        project = tmp_path / "echo_project"
+        (project / "src").mkdir(parents=True)
+        (project / "Cargo.toml").write_text('[package]\nname = "echo_project"\nversion = "0.1.0"\nedition = "2021"\n')
+        (project / "src" / "main.rs").write_text(
+            'use std::io::Read;\n\n'
+            'fn main() {\n'
+            '    let args: Vec<String> = std::env::args().skip(1).collect();\n'
+            '    if args.first().map(String::as_str) == Some("--hang") {\n'
+            '        std::thread::sleep(std::time::Duration::from_secs(30));\n'
+            '    }\n'
+            '    let mut input = String::new();\n'
+            '    std::io::stdin().read_to_string(&mut input).unwrap();\n'
+            '    print!("{} {}", args.join(","), input.to_uppercase());\n'
+            '    std::process::exit(3);\n'
+            '}\n'
+        )
+        builder = CargoBuilder(root_dir=project)
+        
+        result = builder.run(args=["a", "b"], stdin="hello")
+        hung = builder.run(args=["--hang"], timeout=0.5)
+        
+        assert result.stdout == "a,b HELLO"
+        assert result.exit_code == 3
+        assert not result.success
+        assert hung.timed_out
+        assert hung.exit_code is None
+    
+    def test_limits_from_threads(self):
+        """Test that limits reach the spawned process when commands run from worker threads."""
+        from concurrent.futures import ThreadPoolExecutor
+        
+        builder = CargoBuilder(root_dir=Path("test_projects/success_project"),
+                               limits=ResourceLimits(memory_bytes=4 * 1024**3, cpu_seconds=30))
+        
+        with ThreadPoolExecutor(max_workers=4) as pool:
+            results = list(pool.map(lambda _: builder._execute(["sh", "-c", "ulimit -t; ulimit -v"], timeout=30),
+                                    range(8)))
+        
+        assert all(r[0] == 0 and r[1].split() == ["30", str(4 * 1024**2)] for r in results)
+        assert builder.build().success
+    
+    def test_build_timeout(self, tmp_path):
+        """Test that a hanging build script is killed and reported as timed out."""
+        project = tmp_path / "hang_project"
+        (project / "src").mkdir(parents=True)
+        (project / "Cargo.toml").write_text('[package]\nname = "hang_project"\nversion = "0.1.0"\nedition = "2021"\n')
+        (project / "build.rs").write_text("fn main() {\n    std::thread::sleep(std::time::Duration::from_secs(120));\n}\n")
+        (project / "src" / "main.rs").write_text("fn main() {}\n")
+        builder = CargoBuilder(root_dir=project, timeout=5)
+        
+        start = time.monotonic()
+        result = builder.build()
+        
+        assert time.monotonic() - start < 30
+        assert result.status == BuildStatus.TIMED_OUT
+        assert result.timed_out
+        assert not result.success
+    
+    def test_cpu_limit(self, tmp_path):
+        """Test that a program exceeding its CPU time limit is killed."""
+        project = tmp_path / "spin_project"
+        (project / "src").mkdir(parents=True)
+        (project / "Cargo.toml").write_text('[package]\nname = "spin_project"\nversion = "0.1.0"\nedition = "2021"\n')
+        (project / "src" / "main.rs").write_text("fn main() {\n    let mut x: u64 = 0;\n    loop {\n        x = std::hint::black_box(x + 1);\n    }\n}\n")
+        builder = CargoBuilder(root_dir=project, limits=ResourceLimits(cpu_seconds=2))
+        
+        result = builder.run(timeout=60)
+        
+        assert result.build.status == BuildStatus.SUCCEEDED
+        assert not result.timed_out
+        assert not result.success
+        assert result.exit_code != 0
+    
     def test_build_with_errors(self):
         """Test building a project with compilation errors."""
         builder = CargoBuilder(
@@ -109,6 +246,114 @@ class TestCargoBuilder:
         assert "my-package" in cmd
         assert "--message-format" in cmd
         assert "json" in cmd
+    
+    def test_test_command_generation(self):
+        """Test that harness arguments are placed after the separator."""
+        builder = CargoBuilder(root_dir=Path("."))
+        
+        cmd = builder._build_command(
+            subcommand="test",
+            test_args=["--test-threads", "1"],
+        )
+        
+        assert cmd[:2] == ["cargo", "test"]
+        assert cmd[cmd.index("--") + 1:] == ["--test-threads", "1"]
+    
+    def test_run_tests(self):
+        """Test running cargo test and collecting per-test results."""
+        builder = CargoBuilder(
+            root_dir=Path("test_projects/success_project")
+        )
+        result = builder.test()
+        
+        assert result.success is True
+        assert [t.name for t in result.tests] == ["tests::test_factorial"]
+        assert all(t.passed for t in result.tests)
+    
+    def test_cargo_fix(self, tmp_path):
+        """Test applying rustc's suggestions with cargo fix."""
+        project = copy_project("warning_project", tmp_path)
+        builder = CargoBuilder(root_dir=project)
+        
+        result = builder.fix()
+        
+        assert result.fixes == {"src/main.rs": 2}
+        source = (project / "src" / "main.rs").read_text()
+        assert "let result = 5 + 3;" in source
+        assert "let _unused_var = 42;" in source
+        # Only machine-applicable suggestions are applied
+        assert "fn CamelCaseFunction()" in source
+    
+    def test_clippy_fix(self, tmp_path):
+        """Test applying clippy's suggestions with cargo clippy --fix."""
+        project = copy_project("clippy_project", tmp_path)
+        builder = CargoBuilder(root_dir=project)
+        
+        # The NaN comparison fix doesn't compile on its own, which would make cargo roll back every fix
+        result = builder.fix(use_clippy=True, extra_args=["--", "-A", "invalid_nan_comparisons"])
+        
+        assert result.fixes == {"src/main.rs": 5}
+        source = (project / "src" / "main.rs").read_text()
+        assert "return x * 2;" not in source
+        assert 'map.entry("key").or_insert("value");' in source
+        assert "clippy::needless_return" not in [m.code for m in result.messages]
+    
+    def test_apply_suggestions(self, tmp_path):
+        """Test applying machine-applicable suggestions from build messages."""
+        project = copy_project("warning_project", tmp_path)
+        builder = CargoBuilder(root_dir=project)
+        before = builder.build()
+        
+        fixed = apply_suggestions(before.messages, project)
+        after = builder.build()
+        
+        assert [s.message for s in fixed.applied] == [
+            "remove these parentheses",
+            "if this is intentional, prefix it with an underscore",
+        ]
+        assert fixed.files_changed == [project / "src" / "main.rs"]
+        assert len(after.messages) == len(before.messages) - 2
+    
+    def test_shared_target_dir(self, tmp_path):
+        """Test that target directories are partitioned between concurrent holders."""
+        pool = TargetDirPool(tmp_path / "targets", slots=2)
+        
+        with pool.acquire("repo") as first, pool.acquire("repo") as second:
+            assert first != second
+            result = CargoBuilder(root_dir=copy_project("success_project", tmp_path), target_dir=first).build()
+        with pool.acquire("repo") as again:
+            assert again == first
+        
+        assert result.find_executable("success_project").is_relative_to(first)
+    
+    def test_template_checkout(self, tmp_path):
+        """Test that checkouts of a template reuse the dependencies built into it."""
+        project = tmp_path / "app"
+        (project / "src").mkdir(parents=True)
+        (project / "helper" / "src").mkdir(parents=True)
+        (project / "Cargo.toml").write_text(
+            '[package]\nname = "app"\nversion = "0.1.0"\nedition = "2021"\n\n'
+            '[dependencies]\nhelper = { path = "helper" }\n'
+        )
+        (project / "src" / "main.rs").write_text("fn main() {\n    println!(\"{}\", helper::value());\n}\n")
+        (project / "helper" / "Cargo.toml").write_text('[package]\nname = "helper"\nversion = "0.1.0"\nedition = "2021"\n')
+        (project / "helper" / "src" / "lib.rs").write_text("pub fn value() -> u32 {\n    42\n}\n")
+        templates = TemplateCache(tmp_path / "templates", link="hardlink")
+        
+        def prepare(path):
+            CargoBuilder(root_dir=path).build()
+        
+        built = templates.checkout("app@1", project, tmp_path / "one", prepare, workspace_crates=["app"])
+        reused = templates.checkout("app@1", project, tmp_path / "two", prepare, workspace_crates=["app"])
+        (tmp_path / "two" / "src" / "main.rs").write_text("fn main() {\n    let _ = helper::value();\n}\n")
+        result = CargoBuilder(root_dir=tmp_path / "two").build()
+        
+        assert (built, reused) == (False, True)
+        helper_rlib = next((tmp_path / "two" / "target" / "debug" / "deps").glob("libhelper-*.rlib"))
+        assert helper_rlib.stat().st_nlink > 1
+        assert result.success
+        fresh = {a.target_name: a.fresh for a in result.artifacts}
+        assert fresh == {"helper": True, "app": False}
 
 
 class TestCargoOutputParser:
@@ -219,6 +464,192 @@ class TestCargoOutputParser:
         
         # Should skip malformed lines but continue parsing
         assert len(messages) == 0  # Second line lacks required fields
+    
+    def test_parse_human_test_output(self):
+        """Test parsing libtest human output."""
+        parser = CargoOutputParser()
+        
+        with open("test_data/test_output_human.txt", "r") as f:
+            test_output = f.read()
+        
+        results = {t.name: t for t in parser.parse_test_output(test_output)}
+        
+        assert set(results) == {
+            "tests::test_fibonacci",
+            "tests::test_safe_divide",
+            "tests::test_stack",
+        }
+        assert not results["tests::test_fibonacci"].passed
+        assert "left: 0" in results["tests::test_fibonacci"].stdout
+        assert results["tests::test_safe_divide"].passed
+        assert results["tests::test_stack"].stdout is None
+        assert not any(t.doc_test for t in results.values())
+        
+        doc_tests = parser.parse_test_output(
+            "\nrunning 1 test\ntest src/lib.rs - fibonacci (line 3) ... ok\n\ntest result: ok. 1 passed\n"
+        )
+        assert [(t.name, t.doc_test) for t in doc_tests] == [("src/lib.rs - fibonacci (line 3)", True)]
+    
+    def test_parse_test_suites(self):
+        """Test matching each test to the test target it ran in."""
+        parser = CargoOutputParser()
+        stdout = (
+            "\nrunning 1 test\ntest tests::it_works ... ok\n\ntest result: ok. 1 passed\n"
+            "\nrunning 2 tests\ntest a ... ok\ntest b ... FAILED\n\nfailures:\n\n---- b stdout ----\n"
+            "running 2 tests\n\nfailures:\n    b\n\ntest result: FAILED. 1 passed; 1 failed\n"
+        )
+        stderr = (
+            "     Running unittests src/lib.rs (target/debug/deps/suites-fe031b38d220aa33)\n"
+            "     Running tests/hidden.rs (target/debug/deps/hidden-a34c935e9657648c)\n"
+            "error: test failed, to rerun pass `--test hidden`\n"
+            "   Doc-tests suites\n"
+        )
+        
+        suites = parser.parse_test_suites(stderr)
+        results = parser.parse_test_output(stdout, suites)
+        
+        assert suites == ["unittests src/lib.rs", "tests/hidden.rs", "Doc-tests suites"]
+        assert [(t.name, t.suite) for t in results] == [
+            ("tests::it_works", "unittests src/lib.rs"),
+            ("a", "tests/hidden.rs"),
+            ("b", "tests/hidden.rs"),
+        ]
+        # Captured output is not mistaken for another harness
+        assert results[2].stdout == "running 2 tests"
+    
+    def test_parse_json_test_output(self):
+        """Test parsing libtest JSON output."""
+        parser = CargoOutputParser()
+        
+        with open("test_data/test_output_json.txt", "r") as f:
+            test_output = f.read()
+        
+        results = parser.parse_test_output(test_output)
+        
+        assert len(results) == 3
+        assert sum(1 for t in results if t.passed) == 2
+        failed = next(t for t in results if not t.passed)
+        assert failed.name == "tests::test_fibonacci"
+        assert failed.duration is not None
+        assert "left: 0" in failed.stdout
+    
+    def test_parse_artifacts(self):
+        """Test parsing artifacts, build script output and build-finished."""
+        parser = CargoOutputParser()
+        
+        with open("test_data/artifact_output_json.txt", "r") as f:
+            json_output = f.read()
+        
+        artifacts = parser.parse_artifacts(json_output)
+        scripts = parser.parse_build_scripts(json_output)
+        
+        assert [a.kinds for a in artifacts] == [["custom-build"], ["lib"], ["bin"]]
+        assert artifacts[0].is_build_script
+        assert artifacts[1].filenames[0].endswith("libartifact_project.rlib")
+        assert artifacts[1].executable is None
+        assert artifacts[2].executable.endswith("target/debug/artifact_project")
+        assert not any(a.fresh for a in artifacts)
+        assert scripts[0].cfgs == ["has_build_script"]
+        assert scripts[0].env == {"BUILD_GREETING": "hello"}
+        assert parser.parse_build_finished(json_output) is True
+        # Compiler messages are unaffected by the other records
+        assert parser.parse_json_output(json_output) == []
+    
+    def test_parse_json_suggestions(self):
+        """Test parsing suggested replacements and their applicability."""
+        parser = CargoOutputParser()
+        
+        with open("test_data/error_output_json.txt", "r") as f:
+            json_output = f.read()
+        
+        suggestions = [s for m in parser.parse_json_output(json_output) for s in m.suggestions()]
+        by_message = {s.message: s for s in suggestions}
+        
+        semicolon = by_message["add `;` here"]
+        assert semicolon.applicability == Applicability.MACHINE_APPLICABLE
+        assert semicolon.replacements[0].suggested_replacement == ";"
+        assert semicolon.replacements[0].byte_start == semicolon.replacements[0].byte_end
+        assert any(s.applicability == Applicability.MAYBE_INCORRECT for s in suggestions)
+    
+    def test_parse_json_clippy_lints(self):
+        """Test classifying clippy lints by group and default level."""
+        parser = CargoOutputParser()
+        
+        with open("test_data/clippy_output_json.txt", "r") as f:
+            json_output = f.read()
+        
+        lints = {m.code: m.lint for m in parser.parse_json_output(json_output)}
+        
+        assert lints["clippy::eq_op"].group == "correctness"
+        assert lints["clippy::eq_op"].default_level == LintLevel.DENY
+        assert lints["clippy::needless_return"].group == "style"
+        assert lints["clippy::useless_vec"].group == "perf"
+        # rustc lints have a default level but no clippy group
+        assert lints["dead_code"].group is None
+        assert lints["dead_code"].default_level == LintLevel.WARN
+        assert not lints["dead_code"].is_clippy
+    
+    def test_parse_human_clippy_lints(self):
+        """Test recovering clippy lint codes from human-readable output."""
+        parser = CargoOutputParser()
+        
+        with open("test_data/clippy_output_human.txt", "r") as f:
+            human_output = f.read()
+        
+        warnings = [m for m in parser.parse_human_output(human_output) if m.level == MessageLevel.WARNING]
+        groups = {m.code: m.lint_group for m in warnings if m.is_clippy_lint}
+        
+        assert groups["clippy::needless_return"] == "style"
+        assert groups["clippy::map_entry"] == "perf"
+    
+    def test_message_fingerprint(self):
+        """Test that fingerprints ignore line shifts but not the enclosing item."""
+        parser = CargoOutputParser()
+        
+        with open("test_data/clippy_output_json.txt", "r") as f:
+            json_output = f.read()
+        
+        message = next(m for m in parser.parse_json_output(json_output) if m.code == "dead_code")
+        fingerprint = message.fingerprint("main")
+        for span in message.spans:
+            span.line_start += 3
+            span.line_end += 3
+        
+        assert message.primary_span.is_primary
+        assert message.fingerprint("main") == fingerprint
+        assert message.fingerprint("other") != fingerprint
+    
+    def test_lint_registry_from_help_output(self):
+        """Test building a lint registry from clippy-driver -W help."""
+        help_output = """
+Lint checks provided by rustc:
+
+                      name  default  meaning
+                      ----  -------  -------
+                 dead-code  warn     detects unused, unexported items
+
+Lint checks loaded by this crate:
+
+                      name  default  meaning
+                      ----  -------  -------
+   clippy::needless-return  warn     using a return statement like `return expr;` where an expression would suffice
+             clippy::eq-op  deny     equal operands on both sides of a comparison or bitwise combination
+
+Lint groups loaded by this crate:
+
+                      name  sub-lints
+                      ----  ---------
+               clippy::all  clippy::eq-op, clippy::needless-return
+       clippy::correctness  clippy::eq-op
+             clippy::style  clippy::needless-return
+"""
+        registry = LintRegistry.from_help_output(help_output)
+        
+        assert registry.lookup("clippy::needless_return").group == "style"
+        assert registry.lookup("clippy::eq_op").group == "correctness"
+        assert registry.lookup("dead_code").default_level == LintLevel.WARN
+        assert registry.lookup("E0425") is None
+        assert registry.lookup("clippy::needless_return") == LintRegistry.bundled().lookup("clippy::needless_return")
 
 
 class TestIntegration:
@@ -241,6 +672,10 @@ class TestIntegration:
             assert msg.message
             if msg.level == MessageLevel.ERROR:
                 assert msg.rendered  # Should have full rendered output
+        
+        # A failed build still reports that it finished, without the binary
+        assert result.build_finished is False
+        assert result.find_executable("error_project") is None
     
     def test_human_format_integration(self):
         """Test building with human-readable format."""
@@ -0,0 +1,1257 @@
+#!/usr/bin/env python3
+"""
+Test suite for the openzt-eval Rust build scorer.
+"""
+
+import asyncio
+import json
+import subprocess
+import pytest
+from pathlib import Path
+from openzt_eval.scorers import BaseScorer, ScorerResult, RustBuildScorer
+from openzt_eval.test_cases import RustBuildTestCase
+from openzt_eval.run_scorer import RustRunScorer
+from openzt_eval.cleanup_scorer import RustCleanupScorer
+from openzt_eval.crate_scorer import RustCrateScorer
+from openzt_eval.extraction import (
+    find_fenced_blocks,
+    split_slot_response,
+    extract_code,
+    enclosing_function,
+)
+from openzt_eval.patching import PatchError, apply_hunks, apply_patch_answer, parse_patch_answer
+from openzt_eval.rust_syntax import find_item, parse_items, substitute_item, SubstitutionError
+from openzt_eval.evaluator import Evaluator, EvalCase
+from openzt_eval.models import BaseModel, CassetteMiss, ModelConfig, ModelLoader, ModelType
+from openzt_eval.metrics import pass_at_k, pass_at_k_by_group
+from openzt_eval.repositories import CheckoutError
+from openzt_eval.validation import validate_case
+from openzt_eval.cli import create_parser, create_rust_build_scorer
+
+
+FIBONACCI_LIB = '''pub fn fibonacci(n: u32) -> u64 {
+    // TODO: Implement fibonacci function
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fibonacci() {
+        assert_eq!(fibonacci(0), 0);
+        assert_eq!(fibonacci(1), 1);
+        assert_eq!(fibonacci(10), 55);
+    }
+}
+'''
+
+SLOTS_LIB = '''pub fn double(x: i32) -> i32 {
+    // TODO: double
+}
+
+pub fn negate(x: i32) -> i32 {
+    // TODO: negate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_double() {
+        assert_eq!(double(4), 8);
+    }
+
+    #[test]
+    fn test_negate() {
+        assert_eq!(negate(4), -4);
+    }
+}
+'''
+
+FIBONACCI_BODY = '''let (mut a, mut b) = (0u64, 1u64);
+    for _ in 0..n {
+        let next = a + b;
+        a = b;
+        b = next;
+    }
+    a'''
+
+
+@pytest.fixture
+def fixture_crate(tmp_path):
+    """Create a small crate with a single TODO marker."""
+    crate = tmp_path / "fixture_crate"
+    (crate / "src").mkdir(parents=True)
+    (crate / "Cargo.toml").write_text(
+        '[package]\nname = "fixture_crate"\nversion = "0.1.0"\nedition = "2021"\n\n[dependencies]\n'
+    )
+    (crate / "src" / "lib.rs").write_text(FIBONACCI_LIB)
+    return crate
+
+
+def git(repo: Path, *args: str) -> str:
+    """Run git in repo and return its output."""
+    return subprocess.run(
+        ["git", "-c", "user.name=test", "-c", "user.email=test@example.com", *args],
+        cwd=repo, check=True, capture_output=True, text=True
+    ).stdout.strip()
+
+
+@pytest.fixture
+def git_crate(fixture_crate):
+    """Turn the fixture crate into a git repository with the TODO marker tagged v1."""
+    git(fixture_crate, "init", "-q", "-b", "main")
+    git(fixture_crate, "add", "-A")
+    git(fixture_crate, "commit", "-q", "-m", "fixture")
+    git(fixture_crate, "tag", "v1")
+    return fixture_crate
+
+
+def fixture_case(crate: Path, **overrides) -> str:
+    """Serialize a local fixture test case as the scorer's expected value."""
+    data = {
+        "local_path": str(crate),
+        "file_path": "src/lib.rs",
+        "replacement_target": "// TODO: Implement fibonacci function",
+    }
+    data.update(overrides)
+    return json.dumps(data)
+
+
+@pytest.fixture
+def slots_crate(tmp_path):
+    """Create a small crate with two TODO markers."""
+    crate = tmp_path / "slots_crate"
+    (crate / "src").mkdir(parents=True)
+    (crate / "Cargo.toml").write_text(
+        '[package]\nname = "slots_crate"\nversion = "0.1.0"\nedition = "2021"\n\n[dependencies]\n'
+    )
+    (crate / "src" / "lib.rs").write_text(SLOTS_LIB)
+    return crate
+
+
+def slots_case(crate: Path) -> str:
+    """Serialize a two-slot test case as the scorer's expected value."""
+    return json.dumps({
+        "local_path": str(crate),
+        "slots": [
+            {"name": "double", "file_path": "src/lib.rs", "replacement_target": "// TODO: double"},
+            {"name": "negate", "file_path": "src/lib.rs", "replacement_target": "// TODO: negate"},
+        ],
+    })
+
+
+class TestExtraction:
+    """Test extraction of code from model responses."""
+
+    def test_find_fenced_blocks(self):
+        """Test finding fenced blocks with their language and label."""
+        response = "Here you go:\n\n```rust double\nx * 2\n```\n\nAnd:\n~~~\nplain\n~~~\n"
+
+        blocks = find_fenced_blocks(response)
+
+        assert [(b.language, b.label, b.code) for b in blocks] == [
+            ("rust", "double", "x * 2"),
+            (None, None, "plain"),
+        ]
+
+    def test_longer_closing_fence(self):
+        """Test that a block may close with a longer fence of the same kind, but not a shorter one."""
+        response = "```rust\nx * 2\n````\n\n````\n```\nnested\n```\n````\n\n~~~\n-x\n~~~```\n~~~~\n"
+
+        blocks = find_fenced_blocks(response)
+
+        assert [b.code for b in blocks] == ["x * 2", "```\nnested\n```", "-x\n~~~```"]
+
+    def test_split_labelled_blocks(self):
+        """Test splitting a response into slots by block label."""
+        response = "```rust negate\n-x\n```\n```rust double\nx * 2\n```\n```rust other\n0\n```"
+
+        answers = split_slot_response(response, ["double", "negate"])
+
+        assert answers == {"double": "x * 2", "negate": "-x"}
+
+    def test_split_json_reply(self):
+        """Test splitting a structured JSON reply, bare or fenced."""
+        reply = json.dumps({"double": "x * 2", "negate": "-x", "extra": "ignored"})
+
+        assert split_slot_response(reply, ["double", "negate"]) == {"double": "x * 2", "negate": "-x"}
+        assert split_slot_response(f"```json\n{reply}\n```", ["double"]) == {"double": "x * 2"}
+
+    def test_extract_prefers_defining_rust_block(self):
+        """Test picking the block that defines the function over a usage example."""
+        response = (
+            "Here is the implementation:\n\n"
+            "```rust\npub fn double(x: i32) -> i32 {\n    // '}' is not a brace\n    x * 2\n}\n```\n\n"
+            "You can call it like this:\n\n"
+            "```rust\nfn main() {\n    println!(\"{}\", double(21));\n}\n```\n"
+        )
+
+        extraction = extract_code(response, "double")
+
+        assert extraction.source == "fenced_block"
+        assert extraction.blocks_found == 2
+        assert extraction.stripped_signature == "pub fn double(x: i32) -> i32"
+        assert extraction.code == "// '}' is not a brace\nx * 2"
+
+    def test_extract_keeps_unrelated_items(self):
+        """Test that a signature is only stripped for the enclosing function."""
+        code = "fn helper() -> i32 {\n    1\n}"
+
+        assert extract_code(code, "double").code == code
+        assert extract_code(f"```\n{code}", None).source == "unterminated_block"
+
+    def test_enclosing_function(self):
+        """Test finding the function a substitution point sits in."""
+        source = Path("test_projects/rust_eval_test/src/lib.rs").read_text()
+
+        assert enclosing_function(source, source.index("// TODO: Implement pop")) == "pop"
+        assert enclosing_function(source, source.index("pub struct Stack")) is None
+
+
+class TestRustSyntax:
+    """Test item lookup and signature-aware substitution."""
+
+    def test_find_item_path(self):
+        """Test resolving a method path through an impl block."""
+        source = Path("test_projects/rust_eval_test/src/lib.rs").read_text()
+
+        item = find_item(source, "Stack::pop")
+
+        assert item.kind == "fn"
+        assert item.signature == "pub fn pop(&mut self) -> Option<T>"
+        with pytest.raises(LookupError):
+            find_item(source, "Stack::peek")
+
+    def test_substitute_body_and_item(self):
+        """Test that a bare body and a full definition both replace the item."""
+        source = "impl Counter {\n    pub fn get(&self) -> u32 {\n        todo!()\n    }\n}\n"
+
+        body_source, mode = substitute_item(source, "Counter::get", "self.value")
+        assert mode == "body"
+        assert "        self.value\n" in body_source
+        assert "todo!()" not in body_source
+
+        item_source, mode = substitute_item(source, "Counter::get", "pub fn get(&self) -> u32 { self.value }")
+        assert mode == "item"
+        assert "todo!()" not in item_source
+
+    def test_signature_change_rejected(self):
+        """Test that a definition with a different public signature is refused."""
+        source = "pub fn double(x: i32) -> i32 {\n    todo!()\n}\n"
+
+        with pytest.raises(SubstitutionError):
+            substitute_item(source, "double", "pub fn double(x: i64) -> i64 { x * 2 }")
+        # Parameter patterns are not part of the signature
+        substitute_item(source, "double", "pub fn double(mut x: i32) -> i32 { x *= 2; x }")
+
+    def test_braces_in_literals_and_comments(self):
+        """Test that braces inside raw strings, byte and char literals and nested comments aren't matched."""
+        source = (
+            "/* outer /* fn hidden() { */ still a comment } */\n"
+            "pub fn braces(s: &str) -> (&str, char, u8, &[u8]) {\n"
+            "    let _raw = r#\"}\"{ fn fake() {\"#;\n"
+            "    let _string = \"\\\"}\";\n"
+            "    (s, '{', b'}', br\"{{\")\n"
+            "}\n\n"
+            "pub fn after() -> u32 {\n    todo!()\n}\n"
+        )
+
+        braces = find_item(source, "braces")
+        new_source, _ = substitute_item(source, "after", "1")
+
+        assert source[braces.body_start:braces.end].endswith("br\"{{\")\n}")
+        assert [item.path for item in parse_items(source)] == ["braces", "after"]
+        assert new_source == source.replace("    todo!()", "    1")
+
+    def test_lifetimes_and_char_literals(self):
+        """Test telling lifetimes from char literals, which may themselves be quotes or braces."""
+        source = (
+            "impl<'a> Parser<'a> {\n"
+            "    pub fn next(&mut self) -> Option<&'a str> {\n"
+            "        let quotes = ['\\'', '\"', 'a', '}'];\n"
+            "        'outer: loop { break 'outer; }\n"
+            "        todo!()\n"
+            "    }\n"
+            "}\n"
+        )
+
+        item = find_item(source, "Parser::next")
+        new_source, mode = substitute_item(source, "Parser::next", "None")
+
+        assert item.signature == "pub fn next(&mut self) -> Option<&'a str>"
+        assert mode == "body"
+        assert new_source == "impl<'a> Parser<'a> {\n    pub fn next(&mut self) -> Option<&'a str> {\n        None\n    }\n}\n"
+
+    def test_macro_rules_bodies(self):
+        """Test that functions generated by macro_rules! aren't taken for items of the file."""
+        source = (
+            "macro_rules! getter {\n"
+            "    ($name:ident) => {\n"
+            "        pub fn $name(&self) -> u32 { self.$name }\n"
+            "    };\n"
+            "}\n\n"
+            "macro_rules! unit { () => { fn unit() {} } }\n\n"
+            "pub fn unit() -> u32 {\n    todo!()\n}\n"
+        )
+
+        new_source, _ = substitute_item(source, "unit", "0")
+
+        assert [item.path for item in parse_items(source)] == ["unit"]
+        assert find_item(source, "unit").signature == "pub fn unit() -> u32"
+        assert new_source == source.replace("    todo!()", "    0")
+
+
+FIBONACCI_DIFF = '''Here is the fix:
+
+```diff
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,9 @@
+ pub fn fibonacci(n: u32) -> u64 {
+-    // TODO: Implement fibonacci function
++    let (mut a, mut b) = (0u64, 1u64);
++    for _ in 0..n {
++        let next = a + b;
++        a = b;
++        b = next;
++    }
++    a
+ }
+```
+'''
+
+
+class TestPatching:
+    """Test parsing and applying diff and whole-file answers."""
+
+    def test_offset_and_fuzz(self):
+        """Test that hunks apply away from their stated line and with a mismatched context line."""
+        content = "".join(f"line {i}\n" for i in range(1, 21))
+        diff = parse_patch_answer(
+            "--- a/f.txt\n+++ b/f.txt\n"
+            "@@ -2,3 +2,3 @@\n line 10\n-line 11\n+eleven\n line 12\n"
+            "@@ -40,3 +40,3 @@\n line 15\n-line 16\n+sixteen\n changed context\n"
+        ).patches[0]
+
+        patched, hunks = apply_hunks(content, diff.hunks, fuzz=1)
+
+        assert "eleven\nline 12\n" in patched and "sixteen\nline 17\n" in patched
+        assert "line 11\n" not in patched and "line 16\n" not in patched
+        assert hunks == [{"offset": 8, "fuzz": 0}, {"offset": -25, "fuzz": 1}]
+        with pytest.raises(PatchError):
+            apply_hunks(content, diff.hunks, fuzz=0)
+
+    def test_whole_files_and_paths(self, tmp_path):
+        """Test whole-file answers, and that nothing is written unless every change applies."""
+        (tmp_path / "src").mkdir()
+        (tmp_path / "src" / "main.rs").write_text("fn main() {}\n")
+
+        report = apply_patch_answer(tmp_path, parse_patch_answer(
+            "```rust src/main.rs\nfn main() { util::run(); }\n```\n```rust src/util.rs\npub fn run() {}\n```"
+        ))
+
+        assert report == {"src/main.rs": {"action": "replaced"}, "src/util.rs": {"action": "created"}}
+        assert (tmp_path / "src" / "util.rs").read_text() == "pub fn run() {}\n"
+        with pytest.raises(PatchError):
+            apply_patch_answer(tmp_path, parse_patch_answer("```rust ../escape.rs\nfn f() {}\n```"))
+        with pytest.raises(PatchError):
+            parse_patch_answer("```rust\nfn main() {}\n```")
+        with pytest.raises(PatchError):
+            apply_patch_answer(tmp_path, parse_patch_answer(
+                "```rust src/lib.rs\npub mod util;\n```\n```diff\n--- a/src/gone.rs\n+++ b/src/gone.rs\n@@ -1 +1 @@\n-a\n+b\n```"
+            ))
+        assert not (tmp_path / "src" / "lib.rs").exists()
+
+    def test_patch_case(self, fixture_crate):
+        """Test scoring a diff answer, and reporting one that doesn't apply as a patch failure."""
+        scorer = RustBuildScorer(use_clippy=False)
+        expected = json.dumps({"local_path": str(fixture_crate), "response_format": "patch"})
+
+        fixed = scorer.score("Fix the crate", FIBONACCI_DIFF, expected)
+        stale = scorer.score("Fix the crate", FIBONACCI_DIFF.replace("Implement fibonacci", "Implement fib"), expected)
+
+        assert fixed.passed, fixed.reason
+        assert fixed.metadata["patch"]["kind"] == "diff"
+        assert fixed.metadata["patch"]["files"]["src/lib.rs"]["action"] == "modified"
+        assert not stale.passed
+        assert stale.metadata["failure"] == "patch"
+        assert stale.reason.startswith("Patch failed to apply: src/lib.rs: Hunk 1")
+
+    def test_patch_protected_paths(self, fixture_crate):
+        """Test that a patch may not change tests, cargo configuration or, by default, the manifest."""
+        scorer = RustBuildScorer(use_clippy=False)
+        expected = json.dumps({"local_path": str(fixture_crate), "response_format": "patch"})
+        answers = [
+            FIBONACCI_DIFF + "```diff\n--- /dev/null\n+++ b/tests/smoke.rs\n@@ -0,0 +1,2 @@\n+#[test]\n+fn smoke() {}\n```",
+            FIBONACCI_DIFF + "```diff\n--- /dev/null\n+++ b/.cargo/config.toml\n@@ -0,0 +1 @@\n+[build]\n```",
+            FIBONACCI_DIFF + "```diff\n--- /dev/null\n+++ b/build.rs\n@@ -0,0 +1 @@\n+fn main() {}\n```",
+        ]
+
+        for answer in answers:
+            result = scorer.score("Fix the crate", answer, expected)
+            assert result.score == 0.0
+            assert result.metadata["failure"] == "patch"
+            assert result.reason.endswith("may not be changed"), result.reason
+
+        opted_in = json.dumps({"local_path": str(fixture_crate), "response_format": "patch",
+                               "allow_manifest_changes": True})
+        result = scorer.score("Fix the crate", answers[2], opted_in)
+        assert result.passed, result.reason
+
+
+class TestRustBuildTestCase:
+    """Test the RustBuildTestCase configuration."""
+
+    def test_requires_exactly_one_source(self):
+        """Test that a case needs either a repository or a local crate."""
+        with pytest.raises(ValueError):
+            RustBuildTestCase(file_path="src/lib.rs", replacement_target="// TODO")
+
+        with pytest.raises(ValueError):
+            RustBuildTestCase(
+                file_path="src/lib.rs",
+                replacement_target="// TODO",
+                repo_url="https://example.com/repo.git",
+                local_path="test_projects/rust_eval_test",
+            )
+
+    def test_slots_are_exclusive_with_single_target(self):
+        """Test that slots replace, rather than add to, a single target."""
+        with pytest.raises(ValueError):
+            RustBuildTestCase(
+                local_path="test_projects/rust_eval_test",
+                file_path="src/lib.rs",
+                replacement_target="// TODO",
+                slots=[{"name": "a", "file_path": "src/lib.rs", "replacement_target": "// TODO"}],
+            )
+
+    def test_invalid_case_is_scored_zero(self):
+        """Test that an invalid case configuration is reported, not raised."""
+        scorer = RustBuildScorer(use_clippy=False)
+
+        result = scorer.score("prompt", "0", json.dumps({"file_path": "src/lib.rs", "replacement_target": "x"}))
+
+        assert result.score == 0.0
+        assert not result.passed
+        assert "Invalid test case configuration" in result.reason
+
+
+class TestRustBuildScorer:
+    """Test the RustBuildScorer against local fixture crates."""
+
+    def test_local_fixture(self, fixture_crate):
+        """Test that a local crate is copied, substituted and built."""
+        scorer = RustBuildScorer(use_clippy=False)
+
+        result = scorer.score("prompt", FIBONACCI_BODY, fixture_case(fixture_crate))
+
+        assert result.passed
+        assert result.score == 1.0
+        assert result.metadata["tests_passed"] == 1
+        # The fixture itself must be left untouched
+        assert (fixture_crate / "src" / "lib.rs").read_text() == FIBONACCI_LIB
+        assert not (fixture_crate / "target").exists()
+
+    def test_markdown_response(self, fixture_crate):
+        """Test that a chatty, fenced answer with a full definition still builds."""
+        scorer = RustBuildScorer(use_clippy=False)
+        response = (
+            "Sure! Here's an iterative version:\n\n```rust\n"
+            f"pub fn fibonacci(n: u32) -> u64 {{\n    {FIBONACCI_BODY}\n}}\n```\n\nHope this helps."
+        )
+
+        result = scorer.score("prompt", response, fixture_case(fixture_crate))
+
+        assert result.passed
+        assert result.metadata["extraction"]["main"]["stripped_signature"] == "pub fn fibonacci(n: u32) -> u64"
+
+    def test_relative_local_path(self, fixture_crate):
+        """Test that relative local paths are resolved against fixtures_root."""
+        scorer = RustBuildScorer(use_clippy=False, fixtures_root=fixture_crate.parent)
+
+        result = scorer.score("prompt", FIBONACCI_BODY, fixture_case(Path(fixture_crate.name)))
+
+        assert result.passed
+
+    def test_doc_tests_reported_separately(self, fixture_crate):
+        """Test that doc tests don't count towards the pass rate but still fail the case."""
+        lib = fixture_crate / "src" / "lib.rs"
+        lib.write_text("/// ```\n/// assert_eq!(fixture_crate::fibonacci(3), 2);\n/// ```\n\n"
+                       "/// ```\n/// assert_eq!(fixture_crate::fibonacci(4), 4);\n/// ```\n" + lib.read_text())
+        scorer = RustBuildScorer(use_clippy=False)
+
+        result = scorer.score("prompt", FIBONACCI_BODY, fixture_case(fixture_crate))
+
+        assert not result.passed
+        assert result.metadata["tests_passed"] == 1
+        assert result.metadata["test_pass_rate"] == 1.0
+        assert result.metadata["failed_tests"] == []
+        assert result.metadata["doc_tests_passed"] == 1
+        assert result.metadata["doc_tests_failed"] == 1
+        assert "1 of 2 doc tests failed" in result.reason
+
+    def test_failing_tests_reduce_score(self, fixture_crate):
+        """Test that a stub that builds but fails its tests is not a pass."""
+        scorer = RustBuildScorer(use_clippy=False)
+
+        result = scorer.score("prompt", "let _ = n;\n    0", fixture_case(fixture_crate))
+
+        assert result.metadata["build_success"]
+        assert not result.passed
+        assert result.score == 0.0
+        assert result.metadata["failed_tests"] == ["tests::test_fibonacci"]
+
+    def test_hanging_tests_time_out(self, fixture_crate):
+        """Test that tests running past the timeout are killed and score zero."""
+        scorer = RustBuildScorer(use_clippy=False, timeout=10)
+
+        result = scorer.score("prompt", "let _ = n;\n    loop {}", fixture_case(fixture_crate))
+
+        assert result.metadata["build_success"]
+        assert result.metadata["test_status"] == "timed_out"
+        assert result.score == 0.0
+        assert "Tests timed out" in result.reason
+
+    def test_multiple_slots(self, slots_crate):
+        """Test that every slot is substituted from labelled blocks."""
+        scorer = RustBuildScorer(use_clippy=False)
+        response = "```rust double\nx * 2\n```\n\n```rust negate\n-x\n```"
+
+        result = scorer.score("prompt", response, slots_case(slots_crate))
+
+        assert result.passed
+        assert result.metadata["tests_passed"] == 2
+        assert result.metadata["test_case"]["slots"] == ["double", "negate"]
+
+    def test_missing_slot_answer(self, slots_crate):
+        """Test that slots are validated independently before building."""
+        scorer = RustBuildScorer(use_clippy=False)
+
+        result = scorer.score("prompt", json.dumps({"double": "x * 2"}), slots_case(slots_crate))
+
+        assert not result.passed
+        assert result.score == 0.0
+        assert list(result.metadata["slot_errors"]) == ["negate"]
+        assert result.metadata["slots_answered"] == ["double"]
+
+    def test_substituted_regions(self, slots_crate):
+        """Test that the region of each slot is tracked across substitutions in one file."""
+        scorer = RustBuildScorer(use_clippy=False)
+        response = json.dumps({"double": "// doubled\n    x * 2", "negate": "-x"})
+
+        result = scorer.score("prompt", response, slots_case(slots_crate))
+
+        assert result.passed
+        regions = {r["slot"]: (r["line_start"], r["line_end"]) for r in result.metadata["substituted_regions"]}
+        assert regions == {"double": (2, 3), "negate": (7, 7)}
+
+    def test_errors_outside_region_not_counted(self, slots_crate):
+        """Test that an untouched broken stub elsewhere in the crate doesn't count against the answer."""
+        case = json.dumps({
+            "local_path": str(slots_crate),
+            "file_path": "src/lib.rs",
+            "replacement_target": "// TODO: double",
+        })
+        untested = RustBuildScorer(use_clippy=False, run_tests=False)
+
+        result = untested.score("prompt", "x * 2", case)
+        strict = RustBuildScorer(use_clippy=False, run_tests=False, count_outside_diagnostics=True).score(
+            "prompt", "x * 2", case
+        )
+        broken = untested.score("prompt", 'x * "2"', case)
+
+        assert result.passed
+        assert result.score == 1.0
+        assert result.metadata["build_errors"] == 0
+        assert result.metadata["outside_diagnostics"]["errors"] == 1
+        assert "outside the substituted region" in result.reason
+        assert not strict.passed
+        assert not broken.passed
+        assert broken.metadata["build_errors"] >= 1
+
+    def test_tests_not_run_outside_region(self, slots_crate):
+        """Test that a case whose tests can't run because of a stub elsewhere doesn't pass, right or wrong."""
+        case = json.dumps({
+            "local_path": str(slots_crate),
+            "file_path": "src/lib.rs",
+            "replacement_target": "// TODO: double",
+        })
+        scorer = RustBuildScorer(use_clippy=False)
+
+        for answer in ("x * 3", "unimplemented!()", "x * 2"):
+            result = scorer.score("prompt", answer, case)
+            assert not result.passed, answer
+            assert result.score == 0.0
+            assert result.metadata["tests_not_run"]
+            assert result.reason.startswith("Tests not run: build failed with 1 errors outside")
+
+    def test_baseline_delta(self, slots_crate, tmp_path):
+        """Test that diagnostics the pristine crate already has don't count, and baselines are cached."""
+        case = json.dumps({
+            "local_path": str(slots_crate),
+            "file_path": "src/lib.rs",
+            "replacement_target": "// TODO: double",
+        })
+        scorer = RustBuildScorer(use_clippy=False, run_tests=False, count_outside_diagnostics=True,
+                                 baseline=True, baseline_cache_dir=tmp_path / "baselines")
+
+        first = scorer.score("prompt", "x * 2", case)
+        second = scorer.score("prompt", "x * 2", case)
+
+        assert first.passed
+        assert first.metadata["baseline"]["cached"] is False
+        assert first.metadata["baseline"]["build"]["existing"] == 1
+        assert [d["item"] for d in first.metadata["baseline"]["build"]["fixed"]] == ["double"]
+        assert second.metadata["baseline"]["cached"] is True
+        assert len(list((tmp_path / "baselines").iterdir())) == 1
+
+    def test_warm_builds(self, fixture_crate, tmp_path):
+        """Test scoring with a shared target directory and with a prebuilt template."""
+        shared = RustBuildScorer(use_clippy=False, shared_target_dir=tmp_path / "targets")
+        templated = RustBuildScorer(use_clippy=False, template_dir=tmp_path / "templates")
+
+        results = [scorer.score("prompt", FIBONACCI_BODY, fixture_case(fixture_crate))
+                   for scorer in (shared, shared, templated, templated)]
+
+        assert all(result.passed for result in results)
+        assert list((tmp_path / "targets").glob("*/0/debug"))
+        assert len(list((tmp_path / "templates").glob("*/ready"))) == 1
+
+    def test_template_seeds_shared_target_dir(self, fixture_crate, tmp_path):
+        """Test that a template's prebuilt target/ seeds an empty warm directory rather than going unused."""
+        RustBuildScorer(use_clippy=False, template_dir=tmp_path / "templates").score(
+            "prompt", FIBONACCI_BODY, fixture_case(fixture_crate)
+        )
+        (next((tmp_path / "templates").glob("*/tree/target")) / "prebuilt").touch()
+        scorer = RustBuildScorer(use_clippy=False, shared_target_dir=tmp_path / "targets",
+                                 template_dir=tmp_path / "templates")
+
+        results = [scorer.score("prompt", FIBONACCI_BODY, fixture_case(fixture_crate)) for _ in range(2)]
+
+        assert all(result.passed for result in results)
+        assert list((tmp_path / "targets").glob("*/0/prebuilt"))
+
+    def test_git_mirror_worktrees(self, git_crate, tmp_path):
+        """Test that repositories are mirrored once and checked out at a pinned commit."""
+        scorer = RustBuildScorer(use_clippy=False, git_cache_dir=tmp_path / "mirrors")
+        tagged = fixture_case(git_crate, local_path=None, repo_url=str(git_crate), tag_or_branch="v1")
+        branch = fixture_case(git_crate, local_path=None, repo_url=str(git_crate), tag_or_branch="main")
+        sha = git(git_crate, "rev-parse", "HEAD")
+
+        first = scorer.score("prompt", FIBONACCI_BODY, branch)
+        # Branches are resolved once per run, so a new commit doesn't change what is scored
+        (git_crate / "src" / "lib.rs").write_text("")
+        git(git_crate, "commit", "-q", "-am", "empty")
+        second = scorer.score("prompt", FIBONACCI_BODY, branch)
+        third = scorer.score("prompt", FIBONACCI_BODY, tagged)
+
+        assert all(result.passed for result in (first, second, third))
+        assert {result.metadata["revision"]["commit"] for result in (first, second, third)} == {sha}
+        mirrors = list((tmp_path / "mirrors").glob("*.git"))
+        assert len(mirrors) == 1
+        # Worktrees are removed once scored
+        assert len(git(mirrors[0], "worktree", "list").splitlines()) == 1
+
+    def test_unknown_revision(self, git_crate, tmp_path):
+        """Test that a revision that doesn't exist is an error rather than a score."""
+        scorer = RustBuildScorer(use_clippy=False, git_cache_dir=tmp_path / "mirrors")
+        case = fixture_case(git_crate, local_path=None, repo_url=str(git_crate), tag_or_branch="v2")
+
+        with pytest.raises(CheckoutError, match="v2"):
+            scorer.score("prompt", FIBONACCI_BODY, case)
+
+    def test_clippy_group_penalties(self, fixture_crate):
+        """Test that clippy lints are penalized by their clippy group."""
+        scorer = RustBuildScorer(clippy_group_penalties={"style": 0.5})
+        response = FIBONACCI_BODY[:-len("a")] + "return a;"
+
+        result = scorer.score("prompt", response, fixture_case(fixture_crate))
+
+        assert result.metadata["clippy_lints_by_group"] == {"style": 1}
+        # 1.0 - 0.1 for the warning - 0.5 for the style lint
+        assert result.score == pytest.approx(0.4)
+
+    def test_item_path_slots(self, slots_crate):
+        """Test replacing one function's body and another whole function by path."""
+        scorer = RustBuildScorer(use_clippy=False)
+        case = json.dumps({
+            "local_path": str(slots_crate),
+            "slots": [
+                {"name": "double", "file_path": "src/lib.rs", "item_path": "double", "replace": "body"},
+                {"name": "negate", "file_path": "src/lib.rs", "item_path": "negate"},
+            ],
+        })
+        response = "```rust double\nx * 2\n```\n\n```rust negate\npub fn negate(x: i32) -> i32 {\n    -x\n}\n```"
+
+        result = scorer.score("prompt", response, case)
+
+        assert result.passed
+        assert result.metadata["item_substitutions"] == {"double": "body", "negate": "item"}
+
+    def test_hidden_tests(self, fixture_crate):
+        """Test that hidden tests catch an answer that only satisfies the visible ones."""
+        scorer = RustBuildScorer(use_clippy=False)
+        case = fixture_case(fixture_crate, hidden_tests=[
+            {"file_path": "tests/hidden.rs",
+             "content": "#[test]\nfn large() {\n    assert_eq!(fixture_crate::fibonacci(50), 12586269025);\n}\n"},
+            {"file_path": "src/lib.rs",
+             "content": "use super::*;\n\n#[test]\nfn twenty() {\n    assert_eq!(fibonacci(20), 6765);\n}"},
+        ])
+        memorized = "match n {\n        0 => 0,\n        1 => 1,\n        10 => 55,\n        _ => 0,\n    }"
+
+        solved = scorer.score("prompt", FIBONACCI_BODY, case)
+        cheated = scorer.score("prompt", memorized, case)
+
+        assert solved.passed
+        assert solved.metadata["hidden_tests_passed"] == 2
+        assert not cheated.passed
+        assert cheated.metadata["hidden_tests_failed"] == 2
+        # Only the visible tests are named
+        assert cheated.metadata["failed_tests"] == []
+        assert cheated.metadata["tests_passed"] == 1
+
+    def test_case_expectations(self, fixture_crate):
+        """Test the warning budget, penalty overrides and time limit from case metadata."""
+        scorer = RustBuildScorer(use_clippy=False)
+        case = fixture_case(fixture_crate)
+        warning = "let unused = 1;\n    " + FIBONACCI_BODY
+        slow = "std::thread::sleep(std::time::Duration::from_secs(5));\n    " + FIBONACCI_BODY
+
+        allowed = scorer.score("prompt", warning, case)
+        budgeted = scorer.score("prompt", warning, case, {"expected_warnings": 0, "category": "algorithms"})
+        penalized = scorer.score("prompt", warning, case, {"warning_penalty": 0.5})
+        timed_out = scorer.score("prompt", slow, case, {"time_limit": 1})
+        invalid = scorer.score("prompt", FIBONACCI_BODY, case, {"time_limit": "soon"})
+
+        assert allowed.passed
+        assert not budgeted.passed
+        assert "1 warnings, more than the 0 expected" in budgeted.reason
+        assert budgeted.metadata["expectations"] == {"expected_warnings": 0}
+        assert penalized.passed and penalized.score == pytest.approx(0.5)
+        assert timed_out.metadata["test_status"] == "timed_out"
+        assert "Tests timed out after 1 seconds" in timed_out.reason
+        assert "time_limit must be a non-negative number" in invalid.reason
+
+    def test_item_path_signature_change(self, fixture_crate):
+        """Test that an answer changing the public signature scores zero before building."""
+        scorer = RustBuildScorer(use_clippy=False)
+        case = fixture_case(fixture_crate, replacement_target=None, item_path="fibonacci")
+        response = f"pub fn fibonacci(n: u64) -> u64 {{\n    {FIBONACCI_BODY}\n}}"
+
+        result = scorer.score("prompt", response, case)
+
+        assert result.score == 0.0
+        assert "Signature of fibonacci changed" in result.metadata["slot_errors"]["main"]
+
+
+def run_case(**overrides) -> str:
+    """Serialize a run test case against the rust_run_test fixture."""
+    data = {
+        "local_path": "test_projects/rust_run_test",
+        "expected_stdout": "Sum of numbers: 15\n5! = 120\n",
+    }
+    data.update(overrides)
+    return json.dumps(data)
+
+
+class TestRustRunScorer:
+    """Test the RustRunScorer against the rust_run_test fixture."""
+
+    def test_matching_output(self):
+        """Test that a fenced program printing the transcript passes."""
+        scorer = RustRunScorer()
+        response = (
+            "```rust\nfn main() {\n"
+            "    println!(\"Sum of numbers: {}\", (1..=5).sum::<i32>());\n"
+            "    println!(\"5! = {}\", (1..=5).product::<i32>());\n"
+            "}\n```"
+        )
+
+        result = scorer.score("prompt", response, run_case())
+
+        assert result.passed
+        assert result.score == 1.0
+        assert result.metadata["exit_code"] == 0
+
+    def test_partial_output(self):
+        """Test that partly matching output and a wrong exit code get partial credit."""
+        scorer = RustRunScorer()
+        response = 'fn main() {\n    println!("Sum of numbers: 15");\n    std::process::exit(1);\n}'
+
+        result = scorer.score("prompt", response, run_case())
+
+        assert not result.passed
+        # Half the lines match, halved again for the exit code
+        assert result.score == pytest.approx(0.333, abs=0.01)
+        assert "+5! = 120" not in result.metadata["output_diff"]
+        assert "-5! = 120" in result.metadata["output_diff"]
+
+    def test_stdin_and_timeout(self):
+        """Test feeding stdin, and scoring a program that never finishes as zero."""
+        scorer = RustRunScorer(run_timeout=0.5)
+        echo = (
+            "use std::io::Read;\n\nfn main() {\n    let mut input = String::new();\n"
+            "    std::io::stdin().read_to_string(&mut input).unwrap();\n    print!(\"{}\", input);\n}"
+        )
+        hang = "fn main() {\n    loop {}\n}"
+
+        echoed = scorer.score("prompt", echo, run_case(stdin="one\ntwo\n", expected_stdout="one\ntwo"))
+        hung = scorer.score("prompt", hang, run_case())
+
+        assert echoed.passed
+        assert hung.score == 0.0
+        assert hung.metadata["timed_out"]
+
+
+UNTIDY_MAIN = '''fn unused() -> i32 {
+    1
+}
+
+fn main() {
+    let spare = 2;
+    println!("total: {}", 40 + 2);
+}
+'''
+
+
+@pytest.fixture
+def untidy_crate(tmp_path):
+    """Create a binary crate with an unused function and an unused variable."""
+    crate = tmp_path / "untidy_crate"
+    (crate / "src").mkdir(parents=True)
+    (crate / "Cargo.toml").write_text(
+        '[package]\nname = "untidy_crate"\nversion = "0.1.0"\nedition = "2021"\n\n[dependencies]\n'
+    )
+    (crate / "src" / "main.rs").write_text(UNTIDY_MAIN)
+    return crate
+
+
+class TestCleanupScorer:
+    """Test the RustCleanupScorer against a crate with two warnings."""
+
+    def test_diagnostics_removed(self, untidy_crate):
+        """Test that a rewrite without the warnings scores full marks."""
+        scorer = RustCleanupScorer(use_clippy=False)
+        expected = json.dumps({"local_path": str(untidy_crate)})
+        response = '```rust\nfn main() {\n    println!("total: {}", 40 + 2);\n}\n```'
+
+        result = scorer.score("Remove the warnings", response, expected)
+
+        assert result.passed, result.reason
+        assert result.score == 1.0
+        assert result.metadata["original_diagnostics"] == 2
+        assert result.metadata["fixed_diagnostics"] == 2
+        assert result.metadata["output_matches"]
+
+    def test_behavior_change_and_other_files(self, untidy_crate):
+        """Test that changed output scores zero and that only the case's file may change."""
+        scorer = RustCleanupScorer(use_clippy=False)
+        expected = json.dumps({"local_path": str(untidy_crate)})
+
+        changed = scorer.score("prompt", 'fn main() {\n    println!("total: 41");\n}', expected)
+        elsewhere = scorer.score("prompt", "```rust src/lib.rs\npub fn f() {}\n```", expected)
+
+        assert changed.score == 0.0
+        assert "Program output changed" in changed.reason
+        assert elsewhere.score == 0.0
+        assert elsewhere.metadata["failure"] == "patch"
+
+
+GREETER_FILES = '''Here is the crate:
+
+```toml Cargo.toml
+[package]
+name = "greeter"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+```
+
+```rust src/main.rs
+mod greeting;
+
+fn main() {
+    let name = std::env::args().nth(1).unwrap_or_else(|| "world".to_string());
+    println!("{}", greeting::greet(&name));
+}
+```
+
+```rust src/greeting.rs
+pub fn greet(name: &str) -> String {
+    format!("Hello, {name}!")
+}
+```
+'''
+
+GREETER_HIDDEN_TEST = '''#[test]
+fn greets_by_name() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_greeter")).arg("Ferris").output().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "Hello, Ferris!\\n");
+}
+'''
+
+
+class TestCrateScorer:
+    """Test the RustCrateScorer on crates written into a fresh scaffold."""
+
+    def test_generated_crate(self):
+        """Test that the answer's files are built and pass the hidden test of the binary."""
+        scorer = RustCrateScorer(use_clippy=False)
+        expected = json.dumps({
+            "name": "greeter",
+            "hidden_tests": [{"file_path": "tests/cli.rs", "content": GREETER_HIDDEN_TEST}],
+        })
+
+        result = scorer.score("Write a greeter", GREETER_FILES, expected)
+
+        assert result.passed, result.reason
+        assert result.metadata["hidden_tests_passed"] == 1
+        assert result.metadata["files"] == {
+            "Cargo.toml": {"action": "replaced"},
+            "src/main.rs": {"action": "replaced"},
+            "src/greeting.rs": {"action": "created"},
+        }
+
+    def test_rejected_answers(self):
+        """Test that dependencies off the allowlist, renames and files outside src/ are rejected unbuilt."""
+        scorer = RustCrateScorer(use_clippy=False)
+        expected = json.dumps({"name": "greeter", "allowed_dependencies": ["serde"]})
+
+        dependencies = scorer.score("prompt", GREETER_FILES.replace(
+            "[dependencies]\n", '[dependencies]\nserde = "1"\nrx = { package = "regex", version = "1" }\n'
+            'local = { path = "../local" }\n'
+        ), expected)
+        renamed = scorer.score("prompt", GREETER_FILES.replace('name = "greeter"', 'name = "other"'), expected)
+        build_script = scorer.score("prompt", GREETER_FILES.replace("src/greeting.rs", "build.rs"), expected)
+
+        assert dependencies.metadata["failure"] == "manifest"
+        assert dependencies.metadata["manifest_problems"] == [
+            "local is not a crates.io dependency", "regex is not an allowed dependency"
+        ]
+        assert renamed.reason == "Cargo.toml rejected: The package must be named greeter, not other"
+        assert build_script.metadata["failure"] == "answer"
+        assert not (dependencies.passed or renamed.passed or build_script.passed)
+
+    def test_source_overrides_rejected(self):
+        """Test that an allowed crate can't be swapped for other code by [patch], [replace] or cargo config."""
+        scorer = RustCrateScorer(use_clippy=False)
+        expected = json.dumps({"name": "greeter", "allowed_dependencies": ["itoa"]})
+        with_itoa = GREETER_FILES.replace("[dependencies]\n", '[dependencies]\nitoa = "1"\n')
+        vendored = "\n```rust src/vendored/src/lib.rs\npub fn evil() {}\n```\n"
+
+        patched = scorer.score("prompt", with_itoa.replace(
+            "[dependencies]\n", '[patch.crates-io]\nitoa = { path = "src/vendored" }\n\n[dependencies]\n'
+        ) + vendored, expected)
+        replaced = scorer.score("prompt", with_itoa.replace(
+            "[dependencies]\n", '[replace]\n"itoa:1.0.0" = { path = "src/vendored" }\n\n[dependencies]\n'
+        ) + vendored, expected)
+        configured = scorer.score("prompt", with_itoa + (
+            '\n```toml .cargo/config.toml\n[source.crates-io]\nreplace-with = "vendored"\n```\n'
+        ), expected)
+        nested_config = scorer.score("prompt", with_itoa + "\n```toml src/.cargo/config.toml\n[build]\n```\n", expected)
+
+        assert patched.metadata["failure"] == "manifest"
+        assert "[patch] is not allowed" in patched.metadata["manifest_problems"]
+        assert replaced.metadata["failure"] == "manifest"
+        assert "[replace] is not allowed" in replaced.metadata["manifest_problems"]
+        assert configured.metadata["failure"] == "answer"
+        assert nested_config.reason == "Answer rejected: src/.cargo/config.toml: cargo configuration may not be written"
+        assert not (patched.passed or replaced.passed or configured.passed or nested_config.passed)
+
+    def test_build_script_rejected(self):
+        """Test that the manifest can't add a build script."""
+        scorer = RustCrateScorer(use_clippy=False)
+        expected = json.dumps({"name": "greeter"})
+        with_build = GREETER_FILES.replace('edition = "2021"\n', 'edition = "2021"\nbuild = "src/gen.rs"\n') + (
+            "\n```rust src/gen.rs\nfn main() {}\n```\n"
+        )
+
+        result = scorer.score("prompt", with_build, expected)
+
+        assert result.metadata["failure"] == "manifest"
+        assert result.metadata["manifest_problems"] == ["Build scripts are not allowed"]
+
+
+class ScriptedModel(BaseModel):
+    """A model that replays fixed responses and records the conversations it was given."""
+
+    def __init__(self, responses):
+        super().__init__(ModelConfig(name="scripted", type=ModelType.CUSTOM))
+        self.responses = list(responses)
+        self.conversations = []
+
+    async def generate(self, prompt: str, **kwargs) -> str:
+        return await self.chat([{"role": "user", "content": prompt}])
+
+    async def chat(self, messages, **kwargs) -> str:
+        self.conversations.append(list(messages))
+        return self.responses.pop(0)
+
+
+def scripted_evaluator(model: BaseModel, repair_attempts: int, scorer: BaseScorer = None) -> Evaluator:
+    """Create an evaluator for a scripted model with only one Rust scorer, by default the build scorer."""
+    loader = ModelLoader()
+    loader.models["scripted"] = model
+    return Evaluator(loader, scorers=[scorer or RustBuildScorer(use_clippy=False)], use_braintrust=False,
+                     use_autoevals=False, repair_attempts=repair_attempts)
+
+
+class TestRepairLoop:
+    """Test sending compiler feedback back to the model."""
+
+    def test_solved_after_feedback(self, fixture_crate):
+        """Test that diagnostics are sent back and the attempt that passed is recorded."""
+        model = ScriptedModel(["n + \"1\"", FIBONACCI_BODY])
+        case = EvalCase(name="fib", prompt="Implement fibonacci", expected=fixture_case(fixture_crate))
+
+        result = asyncio.run(scripted_evaluator(model, repair_attempts=3).evaluate_case("scripted", case))
+
+        assert result.passed
+        assert result.solved_at == 2
+        assert [a["passed"] for a in result.attempts] == [False, True]
+        feedback = model.conversations[1][-1]["content"]
+        assert model.conversations[1][1] == {"role": "assistant", "content": "n + \"1\""}
+        assert "error[E0" in feedback
+
+    def test_attempts_are_limited(self, fixture_crate):
+        """Test that repair stops after the configured number of attempts."""
+        model = ScriptedModel(["0u64 + \"1\""] * 3)
+        case = EvalCase(name="fib", prompt="Implement fibonacci", expected=fixture_case(fixture_crate))
+
+        result = asyncio.run(scripted_evaluator(model, repair_attempts=2).evaluate_case("scripted", case))
+
+        assert not result.passed
+        assert result.solved_at is None
+        assert len(result.attempts) == 2
+        assert len(model.responses) == 1
+
+    def test_run_scorer_feedback(self):
+        """Test that a program with the wrong output gets the output diff back."""
+        wrong = 'fn main() {\n    println!("Sum of numbers: 14");\n    println!("5! = 120");\n}'
+        right = 'fn main() {\n    println!("Sum of numbers: 15");\n    println!("5! = 120");\n}'
+        model = ScriptedModel([wrong, right])
+        case = EvalCase(name="sum", prompt="Print the sum and factorial", expected=run_case())
+
+        result = asyncio.run(scripted_evaluator(model, 2, RustRunScorer()).evaluate_case("scripted", case))
+
+        assert result.solved_at == 2
+        feedback = model.conversations[1][-1]["content"]
+        assert feedback.startswith("The program did not pass: Output differs from expected")
+        assert "Expected and actual output:\n```\n--- expected\n+++ actual" in feedback
+        assert "-Sum of numbers: 15\n+Sum of numbers: 14" in feedback
+
+
+class TestPassAtK:
+    """Test sampling several responses per case and the pass@k estimate."""
+
+    def test_unbiased_estimator(self):
+        """Test the estimator against its closed form."""
+        from math import comb
+
+        assert pass_at_k(10, 3, 1) == pytest.approx(0.3)
+        assert pass_at_k(10, 3, 5) == pytest.approx(1 - comb(7, 5) / comb(10, 5))
+        assert pass_at_k(10, 0, 10) == 0.0
+        assert pass_at_k(10, 1, 10) == 1.0
+        with pytest.raises(ValueError):
+            pass_at_k(2, 1, 3)
+
+    def test_by_group(self):
+        """Test averaging over cases, and leaving out k larger than a case's samples."""
+        outcomes = [("a", "x", True), ("a", "x", False), ("a", "y", False), ("a", "y", False), ("b", "z", True)]
+
+        summary = pass_at_k_by_group(outcomes, [1, 2])
+
+        assert summary["a"] == {1: pytest.approx(0.25), 2: pytest.approx(0.5)}
+        assert summary["b"] == {1: 1.0, 2: None}
+
+    def test_samples_per_case(self):
+        """Test that every sample is generated and scored independently."""
+        model = ScriptedModel(["", "hello", "hello"])
+        loader = ModelLoader()
+        loader.models["scripted"] = model
+        evaluator = Evaluator(loader, use_braintrust=False, use_autoevals=False, samples=3)
+        case = EvalCase(name="greet", prompt="Say hello", metadata={"category": "chat"})
+
+        results = asyncio.run(evaluator.evaluate([case]))
+
+        assert [(r.sample, r.passed) for r in results] == [(0, False), (1, True), (2, True)]
+        assert evaluator.pass_at_k == [1, 3]
+        with pytest.raises(ValueError):
+            Evaluator(loader, use_braintrust=False, use_autoevals=False, samples=2, pass_at_k=[5])
+
+
+class TestProvenance:
+    """Test recording what produced a result."""
+
+    def test_toolchain_and_revision(self, git_crate):
+        """Test that a result records the toolchain and the commit of a fixture under git."""
+        model = ScriptedModel([FIBONACCI_BODY])
+        case = EvalCase(name="fib", prompt="Implement fibonacci", expected=fixture_case(git_crate))
+
+        result = asyncio.run(scripted_evaluator(model, repair_attempts=1).evaluate_case("scripted", case))
+
+        assert result.passed
+        assert result.provenance["toolchain"]["rustc"].startswith("rustc ")
+        assert result.provenance["revision"]["commit"] == git(git_crate, "rev-parse", "HEAD")
+        assert result.provenance["revision"]["dirty"] is False
+        assert result.provenance["revision"]["tree_digest"]
+
+
+class TestReplay:
+    """Test recording responses to a cassette and replaying them offline."""
+
+    def test_record_then_replay(self, fixture_crate, tmp_path, monkeypatch):
+        """Test that a replay-only run scores the recorded responses without the live model."""
+        import openzt_eval.models
+
+        live = ScriptedModel(["n + \"1\"", FIBONACCI_BODY])
+        create_model = openzt_eval.models.create_model
+        monkeypatch.setattr(openzt_eval.models, "create_model",
+                            lambda config: live if config.type == ModelType.CUSTOM else create_model(config))
+        cassette = tmp_path / "cassette.jsonl"
+        case = EvalCase(name="fib", prompt="Implement fibonacci", expected=fixture_case(fixture_crate))
+
+        def run(record):
+            loader = ModelLoader()
+            loader.load_model(ModelConfig(name="scripted", type=ModelType.REPLAY, endpoint=str(cassette),
+                                          parameters={"temperature": 0.2}, record=record))
+            evaluator = Evaluator(loader, scorers=[RustBuildScorer(use_clippy=False)], use_braintrust=False,
+                                  use_autoevals=False, samples=2)
+            return asyncio.run(evaluator.evaluate([case])), loader.models["scripted"]
+
+        recorded, _ = run(record=ModelConfig(name="scripted", type=ModelType.CUSTOM))
+        replayed, model = run(record=None)
+
+        assert [r.passed for r in recorded] == [False, True]
+        assert [r.response for r in replayed] == [r.response for r in recorded]
+        assert [r.passed for r in replayed] == [False, True]
+        assert model.replayed == 2
+        assert len(live.conversations) == 2
+        with pytest.raises(CassetteMiss):
+            asyncio.run(model.generate("Implement fibonacci"))
+        with pytest.raises(CassetteMiss):
+            asyncio.run(model.generate("Implement fibonacci", temperature=0.9))
+
+
+class TestValidation:
+    """Test checking that cases are solvable without a model."""
+
+    def test_valid_cases(self, fixture_crate, slots_crate):
+        """Test that references pass while empty and todo!() answers fail."""
+        scorer = RustBuildScorer(use_clippy=False)
+        single = EvalCase(name="fib", prompt="Implement fibonacci", expected=fixture_case(fixture_crate),
+                          reference_solution=FIBONACCI_BODY)
+        slots = EvalCase(name="slots", prompt="Implement double and negate", expected=slots_case(slots_crate),
+                         reference_solution={"double": "x * 2", "negate": "-x"})
+
+        for case in (single, slots):
+            validation = validate_case(scorer, case)
+            assert validation.ok, validation.problems
+            assert validation.results["reference"].passed
+            assert not validation.results["placeholder"].passed
+
+    def test_broken_cases(self, fixture_crate, slots_crate):
+        """Test reporting missing targets and references, unrunnable tests, and tests that a placeholder passes."""
+        scorer = RustBuildScorer(use_clippy=False)
+        missing_target = EvalCase(name="missing", prompt="Implement fibonacci", reference_solution=FIBONACCI_BODY,
+                                  expected=fixture_case(fixture_crate, replacement_target="// TODO: gone"))
+        no_reference = EvalCase(name="fib", prompt="Implement fibonacci", expected=fixture_case(fixture_crate))
+        untested = EvalCase(name="fib", prompt="Implement fibonacci", expected=fixture_case(fixture_crate),
+                            reference_solution=FIBONACCI_BODY)
+        other_stub = EvalCase(name="double", prompt="Implement double", reference_solution="x * 2",
+                              expected=json.dumps({"local_path": str(slots_crate), "file_path": "src/lib.rs",
+                                                   "replacement_target": "// TODO: double"}))
+
+        assert validate_case(scorer, missing_target).problems[0].startswith("Cannot be evaluated: ")
+        assert validate_case(scorer, no_reference).problems == ["No reference_solution"]
+        assert validate_case(scorer, other_stub).problems == [
+            "Cannot be evaluated: Tests not run: build failed with 1 errors outside the substituted region"
+        ]
+        assert validate_case(RustBuildScorer(use_clippy=False, run_tests=False), untested).problems == [
+            "A todo!() answer passes"
+        ]
+
+    def test_validate_command(self):
+        """Test that validate takes the scorer options of evaluation runs and builds the same scorer."""
+        parser = create_parser()
+        args = parser.parse_args(["validate", "cases.json", "--case", "fib", "--rust-no-tests",
+                                  "--rust-clippy-group-penalty", "correctness=0.5", "--rust-timeout", "60"])
+        assert args.command == "validate"
+        assert args.test_file == "cases.json" and args.case == ["fib"]
+
+        scorer = create_rust_build_scorer(args)
+        assert not scorer.run_tests
+        assert scorer.timeout == 60
+
+        evaluation = parser.parse_args(["--models", "local:local", "--rust-build", "--rust-no-tests"])
+        assert evaluation.command is None
+        assert not create_rust_build_scorer(evaluation).run_tests
+
+
+class SlowEchoModel(BaseModel):
+    """A model that echoes the prompt after a delay and records how many requests overlapped."""
+
+    def __init__(self):
+        super().__init__(ModelConfig(name="echo", type=ModelType.CUSTOM))
+        self.in_flight = 0
+        self.max_in_flight = 0
+
+    async def generate(self, prompt: str, **kwargs) -> str:
+        self.in_flight += 1
+        self.max_in_flight = max(self.max_in_flight, self.in_flight)
+        # Later prompts finish first
+        await asyncio.sleep(0.05 / len(prompt))
+        self.in_flight -= 1
+        return prompt
+
+
+class TestConcurrency:
+    """Test evaluating cases concurrently."""
+
+    def test_bounded_and_ordered(self):
+        """Test that requests overlap up to the limit and results keep their order."""
+        model = SlowEchoModel()
+        loader = ModelLoader()
+        loader.models["echo"] = model
+        evaluator = Evaluator(loader, use_braintrust=False, use_autoevals=False,
+                              max_concurrent_requests=3, max_concurrent_builds=2)
+        cases = [EvalCase(name=f"case{i}", prompt="x" * i) for i in range(1, 8)]
+
+        results = asyncio.run(evaluator.evaluate(cases))
+
+        assert [r.case_name for r in results] == [c.name for c in cases]
+        assert [r.response for r in results] == [c.prompt for c in cases]
+        assert model.max_in_flight == 3
+
+    def test_broken_case_keeps_other_results(self):
+        """Test that a case whose scoring raises is reported as not evaluated, without losing the rest."""
+        class CheckoutScorer(BaseScorer):
+            def __init__(self):
+                super().__init__("checkout")
+
+            def score(self, prompt, response, expected=None, metadata=None):
+                if prompt == "broken":
+                    raise CheckoutError("no such revision")
+                return ScorerResult(score=1.0, passed=True)
+
+        loader = ModelLoader()
+        loader.models["echo"] = SlowEchoModel()
+        evaluator = Evaluator(loader, scorers=[CheckoutScorer()], use_braintrust=False, use_autoevals=False,
+                              max_concurrent_requests=2, max_concurrent_builds=2)
+        cases = [EvalCase(name="first", prompt="ok"), EvalCase(name="broken", prompt="broken"),
+                 EvalCase(name="last", prompt="ok")]
+
+        results = asyncio.run(evaluator.evaluate(cases))
+        evaluator.close()
+
+        assert [r.case_name for r in results] == ["first", "broken", "last"]
+        assert [r.passed for r in results] == [True, False, True]
+        assert results[1].error == "CheckoutError: no such revision"
+
+
+if __name__ == "__main__":
+    pytest.main([__file__, "-v"])